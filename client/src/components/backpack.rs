@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+use shared::{hero::BackpackAction, messaging::SystemRequest, tag::TagDuration};
+
+use uuid::Uuid;
+
+use crate::ClientState;
+
+/// Shows the backpacks of the player's heroes, and the story tags lying around the scene to pick up.
+#[component]
+pub fn Backpack() -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let mut new_tag = use_signal(String::new);
+    let duration = use_signal(TagDuration::default);
+    let mut recipient = use_signal(String::new);
+
+    let username = client.username.read().clone();
+    let heroes = client.heroes.read().clone();
+    let all_heroes: Vec<String> = heroes.iter().map(|hero| hero.name.clone()).collect();
+    let scene_tags: Vec<(Uuid, String)> =
+        client.scene.read().story_tags.iter().map(|tag| (tag.id(), tag.name().to_string())).collect();
+
+    let send = move |hero: String, action: BackpackAction| {
+        ws.send(SystemRequest::Backpack { username: client.username.read().clone(), hero, action });
+    };
+
+    rsx! {
+        for hero in heroes.into_iter().filter(|hero| hero.player == username) {
+            div { class: "backpack",
+                h4 { "{hero.name}'s backpack" }
+                for item in hero.backpack.iter() {
+                    div { class: "backpack-item",
+                        span { if item.get_value() == 0 { s { "{item.name()}" } } else { "{item.name()}" } }
+                        for (label, action) in [
+                            ("Scratch", BackpackAction::Scratch { tag: item.id() }),
+                            ("Drop", BackpackAction::Drop { tag: item.id() }),
+                            ("Remove", BackpackAction::Remove { tag: item.id() }),
+                        ] {
+                            button {
+                                onclick: {
+                                    let hero = hero.name.clone();
+                                    move |_| send(hero.clone(), action.clone())
+                                },
+                                "{label}"
+                            }
+                        }
+                        select { onchange: move |e| recipient.set(e.value()),
+                            option { value: "", "Give to" }
+                            for other in all_heroes.iter().filter(|other| **other != hero.name) {
+                                option { value: "{other}", "{other}" }
+                            }
+                        }
+                        button {
+                            disabled: recipient().is_empty(),
+                            onclick: {
+                                let hero = hero.name.clone();
+                                let tag = item.id();
+                                move |_| send(hero.clone(), BackpackAction::Transfer { tag, to: recipient() })
+                            },
+                            "Give"
+                        }
+                    }
+                }
+                input {
+                    r#type: "text",
+                    value: new_tag,
+                    placeholder: "New story tag",
+                    oninput: move |e| new_tag.set(e.value()),
+                }
+                super::duration::DurationSelect { duration }
+                button {
+                    disabled: new_tag().trim().is_empty(),
+                    onclick: {
+                        let hero = hero.name.clone();
+                        move |_| {
+                            send(hero.clone(), BackpackAction::Add { name: new_tag(), duration: duration() });
+                            new_tag.set(String::new());
+                        }
+                    },
+                    "Pack"
+                }
+                for (tag, tag_name) in scene_tags.iter().cloned() {
+                    button {
+                        onclick: {
+                            let hero = hero.name.clone();
+                            move |_| send(hero.clone(), BackpackAction::PickUp { tag })
+                        },
+                        "Pick up {tag_name}"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use dioxus::prelude::*;
+use shared::{camp::CampAction, messaging::SystemRequest, tag::TagDuration};
+
+use uuid::Uuid;
+
+use crate::ClientState;
+
+/// Lets each of the player's heroes pick an action while the narrator has a camp open.
+#[component]
+pub fn Camp() -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let mut theme = use_signal(|| None::<Uuid>);
+    let mut prepared = use_signal(String::new);
+    let duration = use_signal(TagDuration::default);
+
+    let Some((name, acted)) = client.open_camp() else {
+        return rsx! {};
+    };
+    let username = client.username.read().clone();
+    let heroes: Vec<_> = client
+        .heroes
+        .read()
+        .iter()
+        .filter(|hero| hero.player == username && !acted.contains(&hero.name))
+        .cloned()
+        .collect();
+
+    let send = move |hero: String, action: CampAction| {
+        ws.send(SystemRequest::CampAction { username: client.username.read().clone(), hero, action });
+    };
+
+    rsx! {
+        div { class: "camp",
+            h3 { "{name}" }
+            for hero in heroes {
+                div { class: "camp-hero",
+                    b { "{hero.name}" }
+                    button {
+                        onclick: {
+                            let hero = hero.name.clone();
+                            move |_| send(hero.clone(), CampAction::Rest)
+                        },
+                        "Rest"
+                    }
+                    button {
+                        onclick: {
+                            let hero = hero.name.clone();
+                            move |_| send(hero.clone(), CampAction::Recover)
+                        },
+                        "Recover"
+                    }
+                    select { onchange: move |e| theme.set(e.value().parse().ok()),
+                        option { value: "", "Theme" }
+                        for t in hero.themes.iter() {
+                            option { value: "{t.id}", "{t.name}" }
+                        }
+                    }
+                    button {
+                        disabled: theme().is_none(),
+                        onclick: {
+                            let hero = hero.name.clone();
+                            move |_| {
+                                if let Some(theme) = theme() {
+                                    send(hero.clone(), CampAction::Reflect { theme });
+                                }
+                            }
+                        },
+                        "Reflect"
+                    }
+                    input {
+                        r#type: "text",
+                        value: prepared,
+                        placeholder: "Story tags, comma separated",
+                        oninput: move |e| prepared.set(e.value()),
+                    }
+                    super::duration::DurationSelect { duration }
+                    button {
+                        disabled: prepared().trim().is_empty(),
+                        onclick: {
+                            let hero = hero.name.clone();
+                            move |_| {
+                                let tags = prepared().split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect();
+                                send(hero.clone(), CampAction::Prepare { tags, duration: duration() });
+                                prepared.set(String::new());
+                            }
+                        },
+                        "Prepare"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Lets the narrator open a camp for the heroes, and close it again once they have acted.
+#[component]
+pub fn CampControls() -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let mut name = use_signal(String::new);
+
+    if *client.role.read() != "narrator" {
+        return rsx! {};
+    }
+    rsx! {
+        div { class: "camp-controls",
+            if client.open_camp().is_some() {
+                button {
+                    onclick: move |_| ws.send(SystemRequest::CloseCamp { role: client.role.read().clone() }),
+                    "Close camp"
+                }
+            } else {
+                input {
+                    r#type: "text",
+                    value: name,
+                    placeholder: "Camp name",
+                    oninput: move |e| name.set(e.value()),
+                }
+                button {
+                    disabled: name().trim().is_empty(),
+                    onclick: move |_| {
+                        ws.send(SystemRequest::OpenCamp { role: client.role.read().clone(), name: name().trim().to_string() });
+                        name.set(String::new());
+                    },
+                    "Open camp"
+                }
+            }
+        }
+    }
+}
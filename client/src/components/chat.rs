@@ -1,5 +1,18 @@
 use dioxus::prelude::*;
-use shared::{messaging::{SystemRequest, SystemResponse}};
+use uuid::Uuid;
+use shared::{
+    command::{parse_roll, Command, CommandError},
+    fairness::RollProof,
+    hero::StatusAction,
+    limit::LimitOutcome,
+    log::LogFormat,
+    messaging::{SystemRequest, SystemResponse},
+    modifier::ModifierMap,
+    probability::Odds,
+    roll::{OutcomeDetails, OutcomeMode},
+    settings::RoomSettings,
+    tag::ExpiryReason,
+};
 
 use crate::ClientState;
 
@@ -9,28 +22,54 @@ pub fn Chat() -> Element {
     let ws = use_context::<Coroutine<SystemRequest>>();
     let username = client.username.read().clone();
     let messages: Vec<_> = client.get_messages();
-    let messages: Vec<_> = messages // only show chat and roll messages
+    let messages: Vec<_> = messages // only show chat, roll and error messages
         .iter()
         .rev()
-        .filter(|item| matches!(item, SystemResponse::Chat {..} | SystemResponse::Roll {..}))
+        .filter(|item| matches!(item, SystemResponse::Chat {..} | SystemResponse::Joined {..} | SystemResponse::Left {..} | SystemResponse::Resync {..} | SystemResponse::Emote {..} | SystemResponse::Whisper {..} | SystemResponse::Roll {..} | SystemResponse::SecretRoll {..} | SystemResponse::PowerSpent {..} | SystemResponse::LimitReached {..} | SystemResponse::StatusChanged {..} | SystemResponse::HeroTakenOut {..} | SystemResponse::Threat {..} | SystemResponse::ReactionRoll {..} | SystemResponse::CampActionTaken {..} | SystemResponse::BackpackChanged {..} | SystemResponse::LootDropped {..} | SystemResponse::TagsExpired {..} | SystemResponse::Revealed {..} | SystemResponse::Undone {..} | SystemResponse::Redone {..} | SystemResponse::LimitExceeded {..} | SystemResponse::Error {..}))
         .collect();
 
-    let mut message_content = use_signal(|| String::new());
+    let mut message_content = use_signal(String::new);
+    let mut is_typing = use_signal(|| false);
+    let mut set_typing = move |typing: bool| {
+        if is_typing() != typing {
+            is_typing.set(typing);
+            ws.send(SystemRequest::Typing { username: client.username.read().clone(), is_typing: typing });
+        }
+    };
+    let typists: Vec<String> = client.typing.read().iter().filter(|typist| **typist != username).cloned().collect();
+    let typists = typists.join(", ");
 
-    let send_chat_message = { // send a chat message to the server
-        let message_content = message_content.clone();
+    let send_chat_message = { // send a chat message or command to the server
         let username = username.clone();
         move || {
-            let msg = SystemRequest::Chat { username: username.clone(), role: "player".into(), content: message_content().clone() };
-            ws.send(msg);
+            let content = message_content();
+            if !content.starts_with('/') {
+                ws.send(SystemRequest::Chat { username: username.clone(), role: client.role.read().clone(), content });
+                return;
+            }
+            match Command::parse(&content).and_then(|command| command_request(client, command)) {
+                Ok(request) => ws.send(request),
+                Err(error) => {
+                    let mut client = client;
+                    client.receive(SystemResponse::Error { message: error.to_string() });
+                }
+            }
         }
     };
 
-    let send_roll = { // send a roll request to the server
+    // the odds of the roll being typed as a command, or of the roll button's plain dice if no roll is being typed
+    let odds = match message_content() {
+        content if content.starts_with('/') => match Command::parse(&content).and_then(|command| command_request(client, command)) {
+            Ok(SystemRequest::Roll { modifiers, .. } | SystemRequest::SecretRoll { modifiers, .. }) => Some(Odds::of(&modifiers)),
+            _ => None,
+        },
+        _ => Some(Odds::of(&ModifierMap::default())),
+    };
+
+    let send_roll = { // roll the dice with no modifiers, which are typed as a /roll command instead
         let username = username.clone();
-        let modifiers = client.current_modifiers.read().clone();
         move |_| {
-            let msg = SystemRequest::Roll { username: username.clone(), modifiers: modifiers.clone() };
+            let msg = SystemRequest::Roll { username: username.clone(), modifiers: ModifierMap::default(), client_seed: client_seed() };
             ws.send(msg);
         }
     };
@@ -48,37 +87,295 @@ pub fn Chat() -> Element {
                                     span { "{content}" }
                                 }
                             },
-                            SystemResponse::Roll {dice_values: (d1, d2), username, modifiers, total} => {
+                            SystemResponse::Joined {username, role} => rsx! {
+                                div { class: "message presence", "{username} joined as {role}" }
+                            },
+                            SystemResponse::Left {username} => rsx! {
+                                div { class: "message presence", "{username} left" }
+                            },
+                            SystemResponse::Resync {missed, ..} => rsx! {
+                                div { class: "message presence", "Missed {missed} updates, the table has been brought up to date" }
+                            },
+                            SystemResponse::Emote {username, content} => rsx! {
+                                div { class: "message emote",
+                                    i { "{username} {content}" }
+                                }
+                            },
+                            SystemResponse::Whisper {from, to, content} => rsx! {
+                                div { class: "message whisper",
+                                    b { "{from} whispers to {to}: " }
+                                    span { "{content}" }
+                                }
+                            },
+                            SystemResponse::SecretRoll {dice_values: (d1, d2), username, modifiers, total, details, proof} => {
+                                let outcome = details.outcome();
+                                let fairness = proof.as_ref().map(|proof| (fairness_check(&client.commitments.read(), proof, (*d1, *d2)), proof_details(proof)));
+                                rsx! {
+                                    div { class: "message roll secret",
+                                        b { "{username} rolled in secret: " }
+                                        span { "({d1}, {d2}) ({modifiers}) = {total}" }
+                                        div { class: "outcome", "{outcome}" }
+                                        if let Some((check, seeds)) = fairness {
+                                            div { class: "fairness", title: "{seeds}", "{check}" }
+                                        }
+                                    }
+                                }
+                            },
+                            SystemResponse::Roll {dice_values: (d1, d2), username, modifiers, total, details, proof, ..} => {
+                                let fairness = proof.as_ref().map(|proof| (fairness_check(&client.commitments.read(), proof, (*d1, *d2)), proof_details(proof)));
                                 let modifiers = modifiers.to_string();
                                 let content = if modifiers.is_empty() { format!("({d1}, {d2}) = {total}") } else { format!("({d1}, {d2}) ({modifiers}) = {total}") };
+                                let outcome = match details {
+                                    OutcomeDetails::Quick { outcome } => format!("{outcome}: {}", outcome.quick_description()),
+                                    OutcomeDetails::Detailed { outcome, power: 0 } => format!("{outcome}"),
+                                    OutcomeDetails::Detailed { outcome, power } => format!("{outcome} with {power} power to spend"),
+                                };
                                 rsx! {
                                     div { class: "message roll",
                                         b { "{username} rolled: " }
                                         span { "{content}" }
+                                        div { class: "outcome", "{outcome}" }
+                                        if let Some((check, seeds)) = fairness {
+                                            div { class: "fairness", title: "{seeds}", "{check}" }
+                                        }
                                     }
                                 }
                             },
+                            SystemResponse::PowerSpent {username, effects, remaining, ..} => {
+                                let content = effects.iter().map(|effect| effect.to_string()).collect::<Vec<_>>().join(", ");
+                                rsx! {
+                                    div { class: "message power",
+                                        b { "{username} spent power: " }
+                                        span { "{content} ({remaining} left)" }
+                                    }
+                                }
+                            },
+                            SystemResponse::LimitReached {challenge, limit, ..} => {
+                                let outcome = match limit.outcome {
+                                    LimitOutcome::Defeated => "is defeated",
+                                    LimitOutcome::Transformed => "is transformed",
+                                    LimitOutcome::Escaped => "escapes",
+                                };
+                                rsx! {
+                                    div { class: "message limit",
+                                        b { "{challenge} {outcome}! " }
+                                        span { "({limit.name} {limit.progress}/{limit.max})" }
+                                    }
+                                }
+                            },
+                            SystemResponse::Threat {hero, status, tier, description, ..} => rsx! {
+                                div { class: "message threat",
+                                    b { "{hero} is threatened with {{{status}:{tier}}}: " }
+                                    span { "{description}" }
+                                }
+                            },
+                            SystemResponse::ReactionRoll {dice_values: (d1, d2), username, total, outcome, status, reduced_by, applied_tier, proof, ..} => {
+                                let fairness = proof.as_ref().map(|proof| (fairness_check(&client.commitments.read(), proof, (*d1, *d2)), proof_details(proof)));
+                                rsx! {
+                                    div { class: "message roll",
+                                        b { "{username} reacted: " }
+                                        span { "({d1}, {d2}) = {total}, {outcome}. {{{status}}} reduced by {reduced_by} to tier {applied_tier}" }
+                                        if let Some((check, seeds)) = fairness {
+                                            div { class: "fairness", title: "{seeds}", "{check}" }
+                                        }
+                                    }
+                                }
+                            },
+                            SystemResponse::CampActionTaken {hero, description, ..} => rsx! {
+                                div { class: "message camp",
+                                    b { "{hero} " }
+                                    span { "{description}" }
+                                }
+                            },
+                            SystemResponse::BackpackChanged {hero, description, ..} => rsx! {
+                                div { class: "message backpack",
+                                    b { "{hero} " }
+                                    span { "{description}" }
+                                }
+                            },
+                            SystemResponse::LootDropped {challenge, tags, ..} => {
+                                let tags = tags.iter().map(|tag| format!("[{tag}]")).collect::<Vec<_>>().join(" ");
+                                rsx! {
+                                    div { class: "message loot",
+                                        b { "{challenge} dropped " }
+                                        span { "{tags}" }
+                                    }
+                                }
+                            },
+                            SystemResponse::TagsExpired {reason, tags, ..} => {
+                                let when = match reason {
+                                    ExpiryReason::Used => "after being used",
+                                    ExpiryReason::SceneEnded => "as the scene ends",
+                                    ExpiryReason::SessionEnded => "as the session ends",
+                                };
+                                let tags = tags.iter().map(|(scope, tag)| format!("[{}] ({scope})", tag.name())).collect::<Vec<_>>().join(" ");
+                                rsx! {
+                                    div { class: "message expired",
+                                        span { "{tags} expired {when}" }
+                                    }
+                                }
+                            },
+                            SystemResponse::Revealed {description, ..} => rsx! {
+                                div { class: "message revealed",
+                                    i { "{description}" }
+                                }
+                            },
+                            SystemResponse::Undone {entry, by} => rsx! {
+                                div { class: "message history",
+                                    i { "{by} undid: {entry}" }
+                                }
+                            },
+                            SystemResponse::Redone {entry, by} => rsx! {
+                                div { class: "message history",
+                                    i { "{by} redid: {entry}" }
+                                }
+                            },
+                            SystemResponse::StatusChanged {hero, description} => rsx! {
+                                div { class: "message status",
+                                    b { "{hero} " }
+                                    span { "{description}" }
+                                }
+                            },
+                            SystemResponse::HeroTakenOut {hero, status} => rsx! {
+                                div { class: "message limit",
+                                    b { "{hero} is taken out " }
+                                    span { "by {status.name()}" }
+                                }
+                            },
+                            SystemResponse::LimitExceeded {limit} => rsx! {
+                                div { class: "message error",
+                                    span { "{limit}" }
+                                }
+                            },
+                            SystemResponse::Error {message} => rsx! {
+                                div { class: "message error",
+                                    span { "{message}" }
+                                }
+                            },
                             _ => rsx! {}
                         }
                     }) }
                 }
             }
+            if let Some(odds) = odds {
+                div { class: "odds", "Odds: {odds}" }
+            }
+            if !typists.is_empty() {
+                div { class: "typing", "{typists} typing…" }
+            }
             div { class: "input-container",
                 input {
                     r#type: "text",
                     value: message_content,
-                    oninput: move |e| message_content.set(e.value()),
+                    oninput: move |e| {
+                        set_typing(!e.value().trim().is_empty());
+                        message_content.set(e.value());
+                    },
                 }
                 button {
                     onclick: move |_| {
                         send_chat_message();
+                        set_typing(false);
                         message_content.set(String::new());
                     },
                     disabled: if message_content().trim() == "" { true },
                     "Send"
                 }
                 button { onclick: send_roll, "Roll" }
+                if *client.role.read() == "narrator" {
+                    label { class: "fair-rolls",
+                        input {
+                            r#type: "checkbox",
+                            checked: client.settings.read().fair_rolls,
+                            onchange: move |event| {
+                                let settings = RoomSettings { fair_rolls: event.checked(), ..client.settings.read().clone() };
+                                ws.send(SystemRequest::RoomSettings { role: client.role.read().clone(), settings });
+                            },
+                        }
+                        "Fair rolls"
+                    }
+                    select {
+                        class: "outcome-mode",
+                        onchange: move |event| {
+                            let outcome_mode = if event.value() == "detailed" { OutcomeMode::Detailed } else { OutcomeMode::Quick };
+                            let settings = RoomSettings { outcome_mode, ..client.settings.read().clone() };
+                            ws.send(SystemRequest::RoomSettings { role: client.role.read().clone(), settings });
+                        },
+                        option { value: "quick", selected: client.settings.read().outcome_mode == OutcomeMode::Quick, "Quick outcomes" }
+                        option { value: "detailed", selected: client.settings.read().outcome_mode == OutcomeMode::Detailed, "Detailed outcomes" }
+                    }
+                    button {
+                        onclick: move |_| ws.send(SystemRequest::EndScene { role: client.role.read().clone() }),
+                        "End scene"
+                    }
+                    button {
+                        onclick: move |_| ws.send(SystemRequest::EndSession { role: client.role.read().clone() }),
+                        "End session"
+                    }
+                }
             }
+            div { class: "session-log",
+                for format in [LogFormat::Markdown, LogFormat::Json] {
+                    a {
+                        href: "http://localhost:3000/log?token={client.token}&format={format.extension()}",
+                        "Download log ({format.extension()})"
+                    }
+                }
+                a { href: "http://localhost:3000/stats?token={client.token}", "Roll statistics" }
+            }
+            super::camp::CampControls {}
+            super::camp::Camp {}
+            super::reaction_prompt::ThreatForm {}
+            super::reaction_prompt::ReactionPrompt {}
+            super::spend_power::SpendPower {}
         }
     }
-}
\ No newline at end of file
+}
+
+/// Turn a chat command into the request it stands for, resolving rolls against the user's hero.
+fn command_request(client: ClientState, command: Command) -> Result<SystemRequest, CommandError> {
+    let username = client.username.read().clone();
+    match command {
+        Command::Roll { modifiers } => {
+            let heroes = client.heroes.read();
+            let hero = heroes.iter().find(|hero| hero.player == username).ok_or(CommandError::Usage("create a hero before rolling"))?;
+            let modifiers = parse_roll(&modifiers, Some(hero), &client.scene.read())?;
+            Ok(SystemRequest::Roll { username, modifiers, client_seed: client_seed() })
+        }
+        Command::SecretRoll { modifiers } => {
+            let modifiers = parse_roll(&modifiers, None, &client.scene.read())?;
+            Ok(SystemRequest::SecretRoll { username, role: client.role.read().clone(), modifiers, client_seed: client_seed() })
+        }
+        Command::Whisper { to, content } => Ok(SystemRequest::Whisper { username, to, content }),
+        Command::Me { content } => Ok(SystemRequest::Emote { username, content }),
+        Command::Status { hero, name, tier } => Ok(SystemRequest::HeroStatus {
+            role: client.role.read().clone(),
+            hero,
+            action: StatusAction::Inflict { name, tier },
+        }),
+    }
+}
+
+/// Make fresh entropy for a roll, which the server mixes into its seed when the table uses fair rolls.
+pub fn client_seed() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Check a fair roll: its seed must match a commitment published before the roll and give the dice rolled.
+fn fairness_check(commitments: &[String], proof: &RollProof, dice_values: (i8, i8)) -> String {
+    if !commitments.contains(&proof.commitment) {
+        return "fair roll: the server's commitment to its seed wasn't seen before the roll".to_string();
+    }
+    match proof.verify(dice_values) {
+        Ok(()) => "fair roll: verified".to_string(),
+        Err(err) => format!("fair roll failed: {err}"),
+    }
+}
+
+/// Describe the seeds behind a fair roll, so anyone can check it for themselves.
+fn proof_details(proof: &RollProof) -> String {
+    format!(
+        "commitment {}\nserver seed {}\nclient seed {}\nnonce {}",
+        proof.commitment, proof.server_seed, proof.client_seed, proof.nonce
+    )
+}
@@ -1,6 +1,7 @@
 use dioxus::prelude::*;
+use gloo_net::http::Request;
 
-use crate::ClientState;
+use crate::{set_up_socket, ClientState};
 
 #[component]
 pub fn Client() -> Element {
@@ -8,10 +9,38 @@ pub fn Client() -> Element {
     rsx! {
         document::Stylesheet { href: asset!("assets/styles/client.css") }
         div { class: "client-container",}
-        if client.username.read().is_empty() {
+        if client.token.read().is_empty() {
             super::login_screen::LoginScreen {}
         } else {
+            Table {}
+        }
+    }
+}
+
+/// The game itself, shown once logged in, with the connection to the campaign.
+#[component]
+fn Table() -> Element {
+    let client = use_context::<ClientState>();
+    let ws_coroutine = set_up_socket(client);
+    let _ = use_context_provider(|| ws_coroutine);
+    let mut creating_hero = use_signal(|| false);
+    let log_out = move |_| async move {
+        let mut client = client;
+        let _ = Request::post(&format!("http://localhost:3000/logout?token={}", client.token.read())).send().await;
+        client.token.set(String::new());
+    };
+    rsx! {
+        button { class: "log-out", onclick: log_out, "Log out" }
+        if creating_hero() {
+            super::hero_creation::HeroCreation { on_close: move |_| creating_hero.set(false) }
+        } else {
+            button { class: "new-hero", onclick: move |_| creating_hero.set(true), "Create hero" }
+            super::sheets::Sheets {}
+            super::backpack::Backpack {}
             super::chat::Chat {}
+            super::presence::Presence {}
+            super::history::History {}
+            super::secrets::Secrets {}
         }
     }
-}
\ No newline at end of file
+}
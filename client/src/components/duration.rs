@@ -0,0 +1,20 @@
+use dioxus::prelude::*;
+use shared::tag::TagDuration;
+
+/// Lets the user choose how long a new story tag lasts.
+#[component]
+pub fn DurationSelect(duration: Signal<TagDuration>) -> Element {
+    rsx! {
+        select {
+            class: "duration",
+            onchange: move |e| {
+                if let Some(chosen) = e.value().parse().ok().and_then(|index: usize| TagDuration::ALL.get(index)) {
+                    duration.set(*chosen);
+                }
+            },
+            for (index, option) in TagDuration::ALL.iter().enumerate() {
+                option { value: "{index}", selected: *option == duration(), "{option}" }
+            }
+        }
+    }
+}
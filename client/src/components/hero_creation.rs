@@ -0,0 +1,134 @@
+use dioxus::prelude::*;
+use gloo_net::http::Request;
+use shared::{
+    messaging::SystemRequest,
+    theme::Theme,
+    themebook::{self, Themebook, HERO_THEMES, STARTING_POWER_TAGS, STARTING_WEAKNESS_TAGS},
+};
+
+use crate::ClientState;
+
+/// Walks the player through building a hero, one theme at a time, from the server's themebook library.
+#[component]
+pub fn HeroCreation(on_close: EventHandler) -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let themebooks = use_resource(|| async {
+        match Request::get("http://localhost:3000/themebooks").send().await {
+            Ok(response) => response.json::<Vec<Themebook>>().await.unwrap_or_default(),
+            Err(err) => {
+                tracing::warn!("Couldn't load themebooks: {err}");
+                vec![]
+            }
+        }
+    });
+    let mut hero_name = use_signal(String::new);
+    let mut themes = use_signal(Vec::<Theme>::new);
+    let mut selected = use_signal(|| None::<usize>);
+    let mut theme_name = use_signal(String::new);
+    let mut power_tags = use_signal(|| vec![String::new(); STARTING_POWER_TAGS]);
+    let mut weakness_tags = use_signal(|| vec![String::new(); STARTING_WEAKNESS_TAGS]);
+    let mut quest = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+
+    let themebooks = themebooks.read().clone().unwrap_or_default();
+    let themebook = selected().and_then(|index| themebooks.get(index).cloned());
+
+    let add_theme = {
+        let themebook = themebook.clone();
+        move |_| {
+            let Some(themebook) = &themebook else { return };
+            match themebook.create_theme(&theme_name(), &power_tags(), &weakness_tags(), &quest()) {
+                Ok(theme) => {
+                    themes.write().push(theme);
+                    selected.set(None);
+                    theme_name.set(String::new());
+                    power_tags.set(vec![String::new(); STARTING_POWER_TAGS]);
+                    weakness_tags.set(vec![String::new(); STARTING_WEAKNESS_TAGS]);
+                    quest.set(String::new());
+                    error.set(None);
+                }
+                Err(err) => error.set(Some(err.to_string())),
+            }
+        }
+    };
+
+    let create_hero = move |_| {
+        let username = client.username.read().clone();
+        match themebook::create_hero(&hero_name(), &username, themes()) {
+            Ok(hero) => {
+                ws.send(SystemRequest::HeroUpdate { username, role: client.role.read().clone(), hero });
+                on_close.call(());
+            }
+            Err(err) => error.set(Some(err.to_string())),
+        }
+    };
+
+    rsx! {
+        div { class: "hero-creation",
+            h2 { "Create a hero" }
+            input {
+                r#type: "text",
+                value: hero_name,
+                placeholder: "Hero name",
+                oninput: move |e| hero_name.set(e.value()),
+            }
+            h3 { "Themes ({themes.read().len()} of {HERO_THEMES})" }
+            ul {
+                for theme in themes.read().iter() {
+                    li { "{theme.name} ({theme.themebook}): {theme.quest}" }
+                }
+            }
+            if themes.read().len() < HERO_THEMES {
+                select { onchange: move |e| selected.set(e.value().parse().ok()),
+                    option { value: "", "Choose a themebook" }
+                    for (index, book) in themebooks.iter().enumerate() {
+                        option { value: "{index}", "{book.name} ({book.might:?})" }
+                    }
+                }
+            }
+            if let Some(book) = themebook {
+                div { class: "themebook",
+                    p { "{book.description}" }
+                    input {
+                        r#type: "text",
+                        value: theme_name,
+                        placeholder: "Theme name",
+                        oninput: move |e| theme_name.set(e.value()),
+                    }
+                    h4 { "Power tags" }
+                    for index in 0..STARTING_POWER_TAGS {
+                        input {
+                            r#type: "text",
+                            value: power_tags.read()[index].clone(),
+                            placeholder: book.power_tag_questions.get(index).cloned().unwrap_or_default(),
+                            oninput: move |e| power_tags.write()[index] = e.value(),
+                        }
+                    }
+                    h4 { "Weakness tags" }
+                    for index in 0..STARTING_WEAKNESS_TAGS {
+                        input {
+                            r#type: "text",
+                            value: weakness_tags.read()[index].clone(),
+                            placeholder: book.weakness_tag_questions.get(index).cloned().unwrap_or_default(),
+                            oninput: move |e| weakness_tags.write()[index] = e.value(),
+                        }
+                    }
+                    h4 { "Quest" }
+                    input {
+                        r#type: "text",
+                        value: quest,
+                        placeholder: book.quest_prompts.first().cloned().unwrap_or_default(),
+                        oninput: move |e| quest.set(e.value()),
+                    }
+                    button { onclick: add_theme, "Add theme" }
+                }
+            }
+            if let Some(error) = error() {
+                div { class: "error", "{error}" }
+            }
+            button { onclick: create_hero, disabled: themes.read().len() < HERO_THEMES, "Create hero" }
+            button { onclick: move |_| on_close.call(()), "Cancel" }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use dioxus::prelude::*;
+use shared::{log::time_of_day, messaging::SystemRequest};
+
+use crate::ClientState;
+
+/// Lists the latest actions taken in the room, with buttons to undo and redo them.
+#[component]
+pub fn History() -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let username = client.username.read().clone();
+    let role = client.role.read().clone();
+    let history = client.history.read().clone();
+    // undo applies to the latest action still standing, and redo to the earliest of the ones undone after it
+    let can_undo = history.iter().rev().find(|entry| !entry.undone).is_some_and(|entry| entry.can_be_undone_by(&username, &role));
+    let can_redo = history.iter().rev().take_while(|entry| entry.undone).last().is_some_and(|entry| entry.can_be_undone_by(&username, &role));
+    rsx! {
+        div { class: "history",
+            h3 { "History" }
+            button { disabled: !can_undo, onclick: move |_| ws.send(SystemRequest::Undo {}), "Undo" }
+            button { disabled: !can_redo, onclick: move |_| ws.send(SystemRequest::Redo {}), "Redo" }
+            for entry in history.iter().rev() {
+                div { class: if entry.undone { "entry undone" } else { "entry" },
+                    span { class: "time", "{time_of_day(entry.at)} " }
+                    b { "{entry.username} " }
+                    span { "{entry.action}" }
+                }
+            }
+        }
+    }
+}
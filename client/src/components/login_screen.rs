@@ -1,32 +1,69 @@
 use dioxus::prelude::*;
+use gloo_net::http::Request;
+use shared::messaging::{LoginRequest, LoginResponse};
 
 use crate::ClientState;
 
+/// Logs in to a campaign with its passphrase, getting the session token the game connects with.
 #[component]
 pub fn LoginScreen() -> Element {
     let mut client = use_context::<ClientState>();
-    let mut name_input = use_signal(|| String::new());
+    let mut campaign_input = use_signal(|| "default".to_string());
+    let mut name_input = use_signal(String::new);
+    let mut passphrase_input = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+
+    let log_in = move |_| async move {
+        let request = LoginRequest { campaign: campaign_input(), username: name_input(), passphrase: passphrase_input() };
+        let response = match Request::post("http://localhost:3000/login").json(&request) {
+            Ok(request) => request.send().await,
+            Err(err) => Err(err),
+        };
+        match response {
+            Ok(response) if response.ok() => match response.json::<LoginResponse>().await {
+                Ok(login) => {
+                    tracing::debug!("Logged in as {} ({})", login.username, login.role);
+                    client.username.set(login.username);
+                    client.role.set(login.role);
+                    client.token.set(login.token);
+                }
+                Err(err) => error.set(Some(err.to_string())),
+            },
+            Ok(response) => error.set(Some(response.text().await.unwrap_or_default())),
+            Err(err) => error.set(Some(err.to_string())),
+        }
+    };
+
     rsx! {
         div { class: "name-container",
             div { class: "name-input",
-                h2 { "Enter your name to join the session" }
+                h2 { "Enter your name and the campaign's passphrase to join the session" }
+                input {
+                    r#type: "text",
+                    value: campaign_input,
+                    placeholder: "Campaign",
+                    oninput: move |e| campaign_input.set(e.value()),
+                }
                 input {
                     r#type: "text",
-                    value: name_input.read().clone(),
+                    value: name_input,
                     placeholder: "Your name",
                     oninput: move |e| name_input.set(e.value()),
                 }
+                input {
+                    r#type: "password",
+                    value: passphrase_input,
+                    placeholder: "Passphrase",
+                    oninput: move |e| passphrase_input.set(e.value()),
+                }
                 button {
-                    onclick: move |_| {
-                        tracing::debug!("Setting username to: {}", name_input.read());
-                        if !name_input.read().trim().is_empty() {
-                            client.username.set(name_input.read().clone());
-                        }
-                        tracing::debug!("Username set to: {}", client.username);
-                    },
-                    disabled: if name_input.read().trim() == "" { true } else { false },
+                    onclick: log_in,
+                    disabled: name_input().trim().is_empty() || passphrase_input().is_empty(),
                     "Join"
                 }
+                if let Some(error) = error() {
+                    div { class: "error", "{error}" }
+                }
             }
         }
     }
@@ -1,3 +1,13 @@
+pub mod backpack;
+pub mod camp;
 pub mod chat;
 pub mod client;
-pub mod login_screen;
\ No newline at end of file
+pub mod duration;
+pub mod hero_creation;
+pub mod history;
+pub mod login_screen;
+pub mod presence;
+pub mod reaction_prompt;
+pub mod secrets;
+pub mod sheets;
+pub mod spend_power;
\ No newline at end of file
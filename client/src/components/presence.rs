@@ -0,0 +1,26 @@
+use dioxus::prelude::*;
+
+use crate::ClientState;
+
+/// Lists who is connected to the room, with their role and whether they're idle or typing.
+#[component]
+pub fn Presence() -> Element {
+    let client = use_context::<ClientState>();
+    let typing = client.typing.read().clone();
+    rsx! {
+        aside { class: "presence",
+            h3 { "Online" }
+            for member in client.roster.read().iter() {
+                div { class: if member.is_idle { "member idle" } else { "member" },
+                    b { "{member.username}" }
+                    span { " ({member.role})" }
+                    if member.is_idle {
+                        span { class: "status", " idle" }
+                    } else if typing.contains(&member.username) {
+                        span { class: "status", " typing…" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use dioxus::prelude::*;
+use shared::{command::parse_roll, messaging::{SystemRequest, SystemResponse}};
+
+use crate::ClientState;
+
+/// Prompts the player to react when the narrator threatens one of their heroes.
+#[component]
+pub fn ReactionPrompt() -> Element {
+    let client = use_context::<ClientState>();
+    let threats = client.pending_threats();
+
+    rsx! {
+        for (threat_id, hero, status, tier, description) in threats {
+            Reaction { key: "{threat_id}", threat_id, hero, status, tier, description }
+        }
+    }
+}
+
+/// One threat against a hero, with the modifiers the player reacts with, written like a roll such as `+Strong -Clumsy`.
+#[component]
+fn Reaction(threat_id: u64, hero: String, status: String, tier: u8, description: String) -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let mut modifiers = use_signal(String::new);
+
+    let react = {
+        let hero = hero.clone();
+        move |_| {
+            let heroes = client.heroes.read();
+            let parsed = parse_roll(&modifiers(), heroes.iter().find(|existing| existing.name == hero), &client.scene.read());
+            match parsed {
+                Ok(modifiers) => ws.send(SystemRequest::ReactionRoll {
+                    username: client.username.read().clone(),
+                    threat_id,
+                    modifiers,
+                    client_seed: super::chat::client_seed(),
+                }),
+                Err(error) => {
+                    let mut client = client;
+                    client.receive(SystemResponse::Error { message: error.to_string() });
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "reaction-prompt",
+            b { "{hero} is threatened with {{{status}:{tier}}}" }
+            span { "{description}" }
+            input {
+                r#type: "text",
+                value: modifiers,
+                placeholder: "+Strong -Clumsy",
+                oninput: move |e| modifiers.set(e.value()),
+            }
+            button { onclick: react, "React" }
+        }
+    }
+}
+
+/// Lets the narrator threaten a hero with a status, which their player then reacts to.
+#[component]
+pub fn ThreatForm() -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let mut hero = use_signal(String::new);
+    let mut status = use_signal(String::new);
+    let mut tier = use_signal(|| 1u8);
+    let mut description = use_signal(String::new);
+
+    if *client.role.read() != "narrator" {
+        return rsx! {};
+    }
+    let heroes: Vec<String> = client.heroes.read().iter().map(|hero| hero.name.clone()).collect();
+
+    rsx! {
+        div { class: "threat-form",
+            select { onchange: move |e| hero.set(e.value()),
+                option { value: "", "Hero" }
+                for name in heroes {
+                    option { value: "{name}", "{name}" }
+                }
+            }
+            input {
+                r#type: "text",
+                value: status,
+                placeholder: "Status",
+                oninput: move |e| status.set(e.value()),
+            }
+            input {
+                r#type: "number",
+                min: 1,
+                max: 6,
+                value: "{tier}",
+                oninput: move |e| tier.set(e.value().parse().unwrap_or(1)),
+            }
+            input {
+                r#type: "text",
+                value: description,
+                placeholder: "What happens",
+                oninput: move |e| description.set(e.value()),
+            }
+            button {
+                disabled: hero().is_empty() || status().trim().is_empty(),
+                onclick: move |_| {
+                    ws.send(SystemRequest::Threat {
+                        role: client.role.read().clone(),
+                        hero: hero(),
+                        status: status().trim().to_string(),
+                        tier: tier(),
+                        description: description(),
+                    });
+                    status.set(String::new());
+                    description.set(String::new());
+                },
+                "Threaten"
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use dioxus::prelude::*;
+use shared::{
+    messaging::SystemRequest,
+    tag::{TagRef, TagScope},
+    visibility::{Secret, Visibility},
+};
+
+use crate::ClientState;
+
+/// Lets the narrator choose how much of each challenge, tag and limit the players can see.
+#[component]
+pub fn Secrets() -> Element {
+    let client = use_context::<ClientState>();
+    if *client.role.read() != "narrator" {
+        return rsx! {};
+    }
+    let scene = client.scene.read().clone();
+    rsx! {
+        div { class: "secrets",
+            h3 { "What the players see" }
+            for tag in scene.story_tags.iter() {
+                SecretControl {
+                    label: format!("[{}]", tag.name()),
+                    secret: Secret::Tag(TagRef::new(TagScope::Scene, tag.id())),
+                    visibility: Visibility::of_tag(&scene.tag_visibility, tag),
+                }
+            }
+            for challenge in scene.challenges.iter() {
+                div { class: "secret-challenge",
+                    SecretControl {
+                        label: challenge.name.clone(),
+                        secret: Secret::Challenge(challenge.name.clone()),
+                        visibility: challenge.visibility,
+                    }
+                    for tag in challenge.tags.iter().chain(&challenge.statuses) {
+                        SecretControl {
+                            label: format!("[{}]", tag.name()),
+                            secret: Secret::Tag(TagRef::new(TagScope::Challenge(challenge.name.clone()), tag.id())),
+                            visibility: Visibility::of_tag(&challenge.tag_visibility, tag),
+                        }
+                    }
+                    for limit in challenge.limits.iter() {
+                        SecretControl {
+                            label: format!("{} limit", limit.name),
+                            secret: Secret::Limit { challenge: challenge.name.clone(), limit: limit.name.clone() },
+                            visibility: limit.visibility,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A secret with buttons for each visibility, the current one disabled.
+#[component]
+fn SecretControl(label: String, secret: Secret, visibility: Visibility) -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    rsx! {
+        div { class: "secret",
+            span { "{label} " }
+            for option in [Visibility::Hidden, Visibility::Partial, Visibility::Revealed] {
+                button {
+                    disabled: option == visibility,
+                    onclick: {
+                        let secret = secret.clone();
+                        move |_| ws.send(SystemRequest::Reveal { role: client.role.read().clone(), secret: secret.clone(), visibility: option })
+                    },
+                    "{option}"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use dioxus::prelude::*;
+use shared::{
+    messaging::SystemRequest,
+    print::{self, PrintDocument},
+    sheet::{self, Sheet, SheetFormat},
+};
+
+use crate::ClientState;
+
+/// Export heroes and challenges to files and printouts, and import them back in.
+#[component]
+pub fn Sheets() -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let mut error = use_signal(|| None::<String>);
+
+    let sheets: Vec<Sheet> = client
+        .heroes
+        .read()
+        .iter()
+        .cloned()
+        .map(Sheet::Hero)
+        .chain(client.scene.read().challenges.iter().cloned().map(Sheet::Challenge))
+        .collect();
+
+    let import = move |evt: FormEvent| async move {
+        let Some(files) = evt.files() else { return };
+        for name in files.files() {
+            let Some(content) = files.read_file_to_string(&name).await else { continue };
+            let Some(format) = SheetFormat::from_file_name(&name) else {
+                error.set(Some(format!("{name} isn't a .json or .toml file")));
+                continue;
+            };
+            let username = client.username.read().clone();
+            let role = client.role.read().clone();
+            match sheet::import_sheet(&content, format) {
+                Ok(Sheet::Hero(mut hero)) => {
+                    hero.player = username.clone();
+                    ws.send(SystemRequest::HeroUpdate { username, role, hero });
+                    error.set(None);
+                }
+                Ok(Sheet::Challenge(challenge)) => {
+                    ws.send(SystemRequest::ChallengeUpdate { role, challenge });
+                    error.set(None);
+                }
+                Err(err) => error.set(Some(format!("{name}: {err}"))),
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "sheets",
+            for sheet in sheets {
+                div { class: "sheet",
+                    span { "{sheet.name()}" }
+                    for format in [SheetFormat::Json, SheetFormat::Toml] {
+                        if let Ok(content) = sheet::export_sheet(&sheet, format) {
+                            a {
+                                href: data_url("text/plain;charset=utf-8", content.as_bytes()),
+                                download: "{sheet.name()}.{format.extension()}",
+                                "Export {format.extension()}"
+                            }
+                        }
+                    }
+                    a {
+                        href: data_url("text/html;charset=utf-8", print_document(&sheet).to_html().as_bytes()),
+                        download: "{sheet.name()}.html",
+                        "Print HTML"
+                    }
+                    a {
+                        href: data_url("application/pdf", &print_document(&sheet).to_pdf()),
+                        download: "{sheet.name()}.pdf",
+                        "Print PDF"
+                    }
+                }
+            }
+            input { r#type: "file", accept: ".json,.toml", onchange: import }
+            if let Some(error) = error() {
+                div { class: "error", "{error}" }
+            }
+        }
+    }
+}
+
+fn print_document(sheet: &Sheet) -> PrintDocument {
+    match sheet {
+        Sheet::Hero(hero) => print::hero_sheet(hero),
+        Sheet::Challenge(challenge) => print::challenge_card(challenge),
+    }
+}
+
+/// Build a data URL so an exported sheet can be downloaded without a round trip to the server.
+fn data_url(mime: &str, content: &[u8]) -> String {
+    let mut url = format!("data:{mime},");
+    for &byte in content {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => url.push(byte as char),
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    url
+}
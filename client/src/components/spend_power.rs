@@ -0,0 +1,129 @@
+use dioxus::prelude::*;
+use shared::{
+    effect::Effect,
+    messaging::SystemRequest,
+    tag::{TagDuration, TagRef, TagScope},
+};
+
+use crate::ClientState;
+
+/// Lets the roller allocate the power from their latest roll to effects.
+#[component]
+pub fn SpendPower() -> Element {
+    let client = use_context::<ClientState>();
+    let ws = use_context::<Coroutine<SystemRequest>>();
+    let mut effects = use_signal(Vec::<Effect>::new);
+    let mut kind = use_signal(|| "status".to_string());
+    let mut target = use_signal(|| None::<usize>);
+    let mut status = use_signal(|| None::<usize>);
+    let mut name = use_signal(String::new);
+    let mut amount = use_signal(|| 1u8);
+    let duration = use_signal(TagDuration::default);
+
+    let Some((roll_id, power)) = client.unspent_power() else {
+        return rsx! {};
+    };
+    // challenges and heroes that can be given statuses, and the statuses they already have
+    let mut targets: Vec<TagScope> = client.scene.read().challenges.iter().map(|c| TagScope::Challenge(c.name.clone())).collect();
+    targets.extend(client.heroes.read().iter().map(|hero| TagScope::Hero(hero.name.clone())));
+    let mut statuses: Vec<(TagRef, String)> = vec![];
+    for challenge in client.scene.read().challenges.iter() {
+        let scope = TagScope::Challenge(challenge.name.clone());
+        statuses.extend(challenge.statuses.iter().map(|s| (TagRef::new(scope.clone(), s.id()), format!("{} ({scope})", s.name()))));
+    }
+    for hero in client.heroes.read().iter() {
+        let scope = TagScope::Hero(hero.name.clone());
+        statuses.extend(hero.statuses.iter().map(|s| (TagRef::new(scope.clone(), s.id()), format!("{} ({scope})", s.name()))));
+    }
+    let cost: u8 = effects.read().iter().map(Effect::cost).sum();
+
+    let add_effect = {
+        let targets = targets.clone();
+        let statuses = statuses.clone();
+        move |_| {
+            let effect = match kind().as_str() {
+                "story" => Effect::CreateStoryTag { name: name(), duration: duration() },
+                "status" => {
+                    let Some(target) = target().and_then(|index| targets.get(index).cloned()) else { return };
+                    Effect::InflictStatus { target, name: name(), tier: amount() }
+                }
+                "reduce" => {
+                    let Some((status, _)) = status().and_then(|index| statuses.get(index).cloned()) else { return };
+                    Effect::ReduceStatus { status, amount: amount() }
+                }
+                "discover" => Effect::Discover { detail: name() },
+                _ => Effect::ExtraFeat { description: name() },
+            };
+            effects.write().push(effect);
+            name.set(String::new());
+        }
+    };
+
+    let spend = {
+        let username = client.username.read().clone();
+        move |_| {
+            ws.send(SystemRequest::SpendPower { username: username.clone(), roll_id, effects: effects() });
+            effects.set(vec![]);
+        }
+    };
+
+    rsx! {
+        div { class: "spend-power",
+            b { "Power to spend: {power - cost.min(power)} of {power}" }
+            ul {
+                for effect in effects.read().iter() {
+                    li { "{effect} ({effect.cost()})" }
+                }
+            }
+            select { onchange: move |e| kind.set(e.value()),
+                option { value: "status", "Give status" }
+                option { value: "reduce", "Reduce status" }
+                option { value: "story", "Create story tag" }
+                option { value: "discover", "Discover" }
+                option { value: "feat", "Extra feat" }
+            }
+            if kind() == "status" {
+                select { onchange: move |e| target.set(e.value().parse().ok()),
+                    option { value: "", "Target" }
+                    for (index, scope) in targets.iter().enumerate() {
+                        option { value: "{index}", "{scope}" }
+                    }
+                }
+            }
+            if kind() == "reduce" {
+                select { onchange: move |e| status.set(e.value().parse().ok()),
+                    option { value: "", "Status" }
+                    for (index, (_, label)) in statuses.iter().enumerate() {
+                        option { value: "{index}", "{label}" }
+                    }
+                }
+            }
+            if matches!(kind().as_str(), "status" | "reduce") {
+                input {
+                    r#type: "number",
+                    min: 1,
+                    max: 6,
+                    value: "{amount}",
+                    oninput: move |e| amount.set(e.value().parse().unwrap_or(1)),
+                }
+            }
+            if kind() == "story" {
+                super::duration::DurationSelect { duration }
+            }
+            if kind() != "reduce" {
+                input {
+                    r#type: "text",
+                    value: name,
+                    placeholder: "Name or detail",
+                    oninput: move |e| name.set(e.value()),
+                }
+            }
+            button {
+                onclick: add_effect,
+                disabled: if kind() == "reduce" { status().is_none() } else { name().trim().is_empty() },
+                "Add"
+            }
+            button { onclick: spend, disabled: effects.read().is_empty() || cost > power, "Spend" }
+        }
+    }
+}
@@ -5,70 +5,188 @@ use dioxus::prelude::*;
 use futures::{SinkExt, StreamExt};
 use gloo_net::websocket::{futures::WebSocket, Message};
 
-use shared::{messaging::{SystemRequest, SystemResponse}, tag::{Tag}, modifier::ModifierMap};
+use shared::{hero::Hero, history::AuditEntry, messaging::{SystemRequest, SystemResponse}, presence::Member, settings::RoomSettings, scene::Scene};
 
 fn main() {
     launch(|| {
         tracing::info!("Starting Client");
-        let (ws_coroutine, message_list) = set_up_socket();
         let client_state = ClientState {
-            messages: message_list,
-            username: use_signal(|| "Ben".to_string()),
-            current_modifiers: use_signal(|| {
-                let mut mod_map = ModifierMap::default();
-                mod_map.add_positive_tag(Tag::new_power("Strong"));
-                mod_map.add_burned_tag(Tag::new_power("Agile"));
-                mod_map.add_negative_tag(Tag::new_weakness("Clumsy"));
-                mod_map.add_negative_tag(Tag::new_status("Poisoned", 4));
-                mod_map
-            }),
+            messages: use_signal(Vec::new),
+            username: use_signal(String::new),
+            role: use_signal(|| "player".to_string()),
+            token: use_signal(String::new),
+            scene: use_signal(Scene::default),
+            heroes: use_signal(Vec::new),
+            roster: use_signal(Vec::new),
+            typing: use_signal(Vec::new),
+            history: use_signal(Vec::new),
+            settings: use_signal(RoomSettings::default),
+            commitments: use_signal(Vec::new),
         };
-        let _ = use_context_provider(|| ws_coroutine);
         let _ = use_context_provider(|| client_state);
         rsx! { components::client::Client {} }
     });
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 struct ClientState {
     messages: Signal<Vec<SystemResponse>>,
     username: Signal<String>,
-    current_modifiers: Signal<ModifierMap>,
+    role: Signal<String>,
+    token: Signal<String>,
+    scene: Signal<Scene>,
+    heroes: Signal<Vec<Hero>>,
+    roster: Signal<Vec<Member>>,
+    typing: Signal<Vec<String>>,
+    history: Signal<Vec<AuditEntry>>,
+    settings: Signal<RoomSettings>,
+    /// Every commitment to a seed the server has published, so fair rolls can be checked against them.
+    commitments: Signal<Vec<String>>,
 }
 
 impl ClientState {
     fn get_messages(&self) -> Vec<SystemResponse> {
         self.messages.read().to_vec()
     }
+
+    /// Apply a response from the server to the client state.
+    fn receive(&mut self, msg: SystemResponse) {
+        match &msg {
+            SystemResponse::SceneUpdate { scene } => self.scene.set(scene.clone()),
+            SystemResponse::ChallengeUpdate { challenge } => self.scene.write().upsert_challenge(challenge.clone()),
+            SystemResponse::HeroUpdate { hero } => {
+                let mut heroes = self.heroes.write();
+                match heroes.iter_mut().find(|existing| existing.name == hero.name) {
+                    Some(existing) => *existing = hero.clone(),
+                    None => heroes.push(hero.clone()),
+                }
+            }
+            SystemResponse::Roster { members } => self.roster.set(members.clone()),
+            SystemResponse::RoomSettings { settings } => self.settings.set(settings.clone()),
+            SystemResponse::Resync { settings, scene, heroes, members, .. } => {
+                self.settings.set(settings.clone());
+                self.scene.set(scene.clone());
+                self.heroes.set(heroes.clone());
+                self.roster.set(members.clone());
+            }
+            SystemResponse::StateRestored { settings, scene, heroes } => {
+                self.settings.set(settings.clone());
+                self.scene.set(scene.clone());
+                self.heroes.set(heroes.clone());
+            }
+            SystemResponse::RollCommitment { commitment, .. } => {
+                self.commitments.write().push(commitment.clone());
+                return;
+            }
+            SystemResponse::History { entries } => {
+                self.history.set(entries.clone());
+                return;
+            }
+            SystemResponse::Joined { username, role } => {
+                let mut roster = self.roster.write();
+                roster.retain(|member| member.username != *username);
+                roster.push(Member { username: username.clone(), role: role.clone(), is_idle: false });
+                roster.sort_by(|a, b| a.username.cmp(&b.username));
+            }
+            SystemResponse::Left { username } => {
+                self.roster.write().retain(|member| member.username != *username);
+                self.typing.write().retain(|typist| typist != username);
+            }
+            SystemResponse::Idle { username, is_idle } => {
+                if let Some(member) = self.roster.write().iter_mut().find(|member| member.username == *username) {
+                    member.is_idle = *is_idle;
+                }
+                return;
+            }
+            SystemResponse::Typing { username, is_typing } => {
+                let mut typing = self.typing.write();
+                typing.retain(|typist| typist != username);
+                if *is_typing {
+                    typing.push(username.clone());
+                }
+                return; // typing comes and goes too often to keep in the log
+            }
+            _ => (),
+        }
+        self.messages.write().push(msg);
+    }
+
+    /// Get the unresolved threats against the user's heroes as (id, hero, status, tier, description).
+    fn pending_threats(&self) -> Vec<(u64, String, String, u8, String)> {
+        let username = self.username.read().clone();
+        let messages = self.messages.read();
+        let mut threats = vec![];
+        for msg in messages.iter() {
+            match msg {
+                SystemResponse::Threat { threat_id, hero, player, status, tier, description } if *player == username => {
+                    threats.push((*threat_id, hero.clone(), status.clone(), *tier, description.clone()));
+                }
+                SystemResponse::ReactionRoll { threat_id, .. } => threats.retain(|(id, ..)| id != threat_id),
+                _ => (),
+            }
+        }
+        threats
+    }
+
+    /// Get the name of the open camp and the heroes that have already acted in it, if the narrator has opened one.
+    fn open_camp(&self) -> Option<(String, Vec<String>)> {
+        let mut camp = None;
+        for msg in self.messages.read().iter() {
+            match msg {
+                SystemResponse::CampOpened { name } => camp = Some((name.clone(), vec![])),
+                SystemResponse::CampActionTaken { hero, .. } => {
+                    if let Some((_, acted)) = camp.as_mut() {
+                        acted.push(hero.clone());
+                    }
+                }
+                SystemResponse::CampClosed {} => camp = None,
+                _ => (),
+            }
+        }
+        camp
+    }
+
+    /// Get the id and remaining power of the user's latest roll, if it has power left to spend.
+    fn unspent_power(&self) -> Option<(u64, u8)> {
+        let username = self.username.read().clone();
+        let messages = self.messages.read();
+        let mut unspent = None;
+        for msg in messages.iter() {
+            match msg {
+                SystemResponse::Roll { roll_id, username: roller, details, .. } if *roller == username => {
+                    unspent = Some((*roll_id, details.power()));
+                }
+                SystemResponse::PowerSpent { roll_id, remaining, .. } if unspent.map(|(id, _)| id) == Some(*roll_id) => {
+                    unspent = Some((*roll_id, *remaining));
+                }
+                _ => (),
+            }
+        }
+        unspent.filter(|(_, power)| *power > 0)
+    }
 }
 
-fn set_up_socket() -> (Coroutine<SystemRequest>, Signal<Vec<SystemResponse>>) {
+fn set_up_socket(mut client: ClientState) -> Coroutine<SystemRequest> {
     tracing::info!("Creating WebSocket connection...");
     let mut receiver_ws = use_signal(|| None); // will receive the websocket responses
     let ws_client = use_coroutine(move |mut rx: UnboundedReceiver<SystemRequest>| async move { // will send websocket requests received from the client into this coroutine
-        let (mut sender, receiver) = WebSocket::open("ws://localhost:3000/message").unwrap().split(); //  split the websocket into a sender and receiver
+        let (mut sender, receiver) = WebSocket::open(&format!("ws://localhost:3000/message?token={}", client.token.read())).unwrap().split(); //  split the websocket into a sender and receiver
         receiver_ws.set(Some(receiver)); // store the receiver in a signal so it can be used in another coroutine
         while let Some(msg) = rx.next().await { // wait for messages from the client
             tracing::debug!("Sending message: {:?}", msg);
             sender.send(Message::Text(serde_json::to_string(&msg).unwrap())).await.unwrap(); // send the message to the server
         }
     });
-    let mut message_list: Signal<Vec<SystemResponse>> = use_signal(|| vec![]); // will store the list of messages received from the server
     use_future(move || async move { // coroutine to handle incoming websocket messages
         if let Some(mut receiver) = receiver_ws.take() { // get the receiver from the signal
             while let Some(msg) = receiver.next().await { // wait for messages from the server
-                if let Ok(msg) = msg { // if the message is valid
-                    match msg { // match on the message type
-                        Message::Text(content) => { // if it's a text message
-                            let msg = serde_json::from_str(&content).unwrap();
-                            tracing::debug!("Received message: {:?}", msg);
-                            message_list.write().push(msg);
-                        },
-                        _ => () // ignore other message types
-                    }
+                if let Ok(Message::Text(content)) = msg { // if it's a valid text message, ignoring other message types
+                    let msg = serde_json::from_str(&content).unwrap();
+                    tracing::debug!("Received message: {:?}", msg);
+                    client.receive(msg);
                 }
             }
         }
     });
-    (ws_client, message_list)
-}
\ No newline at end of file
+    ws_client
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use shared::messaging::{LoginRequest, LoginResponse};
+use uuid::Uuid;
+
+use crate::campaigns::Campaign;
+
+/// How long a session lasts without being used or connected before it ends and its name is free again.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Role names, which can't be used as names since whispers and secret rolls can be addressed to a role.
+const ROLE_NAMES: [&str; 2] = ["narrator", "player"];
+
+/// Reasons a login can be refused.
+#[derive(Debug)]
+pub enum LoginError {
+    UnknownCampaign(String),
+    WrongPassphrase,
+    NoName,
+    NameTaken(String),
+    ReservedName(String),
+}
+
+impl std::fmt::Display for LoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoginError::UnknownCampaign(name) => write!(f, "there is no campaign called {name}"),
+            LoginError::WrongPassphrase => write!(f, "that passphrase isn't right for this campaign"),
+            LoginError::NoName => write!(f, "you need a name to join"),
+            LoginError::NameTaken(name) => write!(f, "someone is already playing as {name}"),
+            LoginError::ReservedName(name) => write!(f, "{name} is a role, so it can't be used as a name"),
+        }
+    }
+}
+
+/// Who a session token belongs to.
+#[derive(Clone)]
+pub struct Session {
+    pub campaign: String,
+    pub username: String,
+    pub role: String,
+}
+
+/// A session that has been handed out, and how it's being used.
+struct Issued {
+    session: Session,
+    last_used: Instant,
+    /// How many connections are open with the session's token.
+    connections: usize,
+}
+
+impl Issued {
+    fn is_expired(&self) -> bool {
+        self.connections == 0 && self.last_used.elapsed() >= SESSION_TIMEOUT
+    }
+}
+
+/// The sessions handed out at login, by token.
+#[derive(Default)]
+pub struct Sessions {
+    sessions: HashMap<String, Issued>,
+}
+
+impl Sessions {
+    /// Check a login against the campaign's passphrases and start a session for it.
+    /// A name belongs to the session that took it while that session is connected, so nobody else can act as that user.
+    /// Once it has disconnected, as when a page is reloaded, logging in again with the same role takes the name over
+    /// and ends the old session.
+    pub fn login(&mut self, campaign: Option<&Campaign>, request: LoginRequest) -> Result<LoginResponse, LoginError> {
+        let campaign = campaign.ok_or_else(|| LoginError::UnknownCampaign(request.campaign.clone()))?;
+        let role = campaign.role_for(&request.passphrase).ok_or(LoginError::WrongPassphrase)?;
+        let username = request.username.trim().to_string();
+        if username.is_empty() {
+            return Err(LoginError::NoName);
+        }
+        if ROLE_NAMES.iter().any(|role| username.eq_ignore_ascii_case(role)) {
+            return Err(LoginError::ReservedName(username));
+        }
+        self.sessions.retain(|_, issued| !issued.is_expired());
+        let held = self
+            .sessions
+            .iter()
+            .find(|(_, issued)| issued.session.campaign == campaign.config.name && issued.session.username == username);
+        if let Some((token, issued)) = held {
+            if issued.connections > 0 || issued.session.role != role {
+                return Err(LoginError::NameTaken(username));
+            }
+            tracing::info!("{username} took over their disconnected session in {}", campaign.config.name);
+            let token = token.clone();
+            self.sessions.remove(&token);
+        }
+
+        let token = Uuid::new_v4().to_string();
+        let session = Session { campaign: campaign.config.name.clone(), username: username.clone(), role: role.to_string() };
+        self.sessions.insert(token.clone(), Issued { session, last_used: Instant::now(), connections: 0 });
+        tracing::info!("{username} logged in to {} as {role}", campaign.config.name);
+        Ok(LoginResponse { token, username, role: role.to_string() })
+    }
+
+    /// Get the session a token was issued for, if it hasn't ended, keeping it going.
+    pub fn get(&mut self, token: &str) -> Option<Session> {
+        let issued = self.sessions.get_mut(token)?;
+        if issued.is_expired() {
+            self.sessions.remove(token);
+            return None;
+        }
+        issued.last_used = Instant::now();
+        Some(issued.session.clone())
+    }
+
+    /// Note that a connection has opened with a session's token. The session doesn't expire while it's connected.
+    pub fn connected(&mut self, token: &str) {
+        if let Some(issued) = self.sessions.get_mut(token) {
+            issued.connections += 1;
+        }
+    }
+
+    /// Note that a connection with a session's token has closed, starting the timeout once none are left.
+    pub fn disconnected(&mut self, token: &str) {
+        if let Some(issued) = self.sessions.get_mut(token) {
+            issued.connections = issued.connections.saturating_sub(1);
+            issued.last_used = Instant::now();
+        }
+    }
+
+    /// End a session straight away, freeing its name, returning the session if there was one.
+    pub fn revoke(&mut self, token: &str) -> Option<Session> {
+        self.sessions.remove(token).map(|issued| issued.session)
+    }
+}
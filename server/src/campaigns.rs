@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+use shared::{
+    log::SessionLog,
+    messaging::{SystemRequest, SystemResponse},
+};
+use tokio::sync::broadcast::{Sender, channel};
+
+use crate::{
+    connections::Connections,
+    events::EventStore,
+    history,
+    limits::Limits,
+    metrics::Metrics,
+    room::{Room, RoomError},
+    stats::RollStats,
+};
+
+/// The file campaigns are loaded from when `CAMPAIGNS_FILE` isn't set.
+pub const DEFAULT_CAMPAIGNS_FILE: &str = "campaigns.toml";
+
+/// A campaign as written in the campaigns file, with the passphrases players and the narrator join with.
+#[derive(Deserialize)]
+pub struct CampaignConfig {
+    pub name: String,
+    pub passphrase: String,
+    pub narrator_passphrase: String,
+    #[serde(default)]
+    pub limits: Limits,
+}
+
+#[derive(Deserialize)]
+struct CampaignsFile {
+    campaigns: Vec<CampaignConfig>,
+}
+
+/// A campaign's room, and the connections playing in it.
+pub struct Campaign {
+    pub config: CampaignConfig,
+    pub tx: Sender<SystemResponse>,
+    pub room: Mutex<Room>,
+    pub connections: Mutex<Connections>,
+    pub metrics: Metrics,
+    /// Everything sent to the whole table since the server started.
+    pub log: Mutex<SessionLog>,
+    /// The log every change to the room is recorded in, if it could be opened.
+    pub events: Option<Mutex<EventStore>>,
+    pub stats: Mutex<RollStats>,
+}
+
+impl Campaign {
+    /// Create a campaign, rebuilding its room from its event log in the given directory.
+    fn new(config: CampaignConfig, events_dir: &Path) -> Self {
+        let (tx, _) = channel(100);
+        let log = Mutex::new(SessionLog::new(&config.name));
+        let (room, events) = match EventStore::open(&events_dir.join(&config.name)) {
+            Ok((events, room)) => (room, Some(Mutex::new(events))),
+            Err(err) => {
+                tracing::warn!("Couldn't open the event log for {}, so its room won't be kept: {err}", config.name);
+                (Room::default(), None)
+            }
+        };
+        Campaign { config, tx, room: Mutex::new(room), connections: Mutex::default(), metrics: Metrics::default(), log, events, stats: Mutex::default() }
+    }
+
+    /// Handle a request in the campaign's room, recording the events it causes in the event log and any rolls in the statistics.
+    /// Events are handed to the log while the room is still locked, so they're written in the order they happened,
+    /// but the log writes them out on its own task rather than holding anything up.
+    pub fn handle(&self, username: &str, role: &str, request: SystemRequest) -> Result<Vec<SystemResponse>, RoomError> {
+        let ends_session = matches!(request, SystemRequest::EndSession { .. });
+        let mut room = self.room.lock().unwrap();
+        let responses = room.handle(username, role, request)?;
+        {
+            let mut stats = self.stats.lock().unwrap();
+            responses.iter().for_each(|response| stats.record(response));
+            if ends_session {
+                stats.end_session();
+            }
+        }
+        // typing comes and goes too often to be worth keeping
+        let events: Vec<_> = responses.iter().filter(|response| !matches!(response, SystemResponse::Typing { .. })).cloned().collect();
+        if let (Some(store), false) = (&self.events, events.is_empty())
+            && let Err(err) = store.lock().unwrap().append(events, &room)
+        {
+            tracing::error!("Couldn't record events for {}: {err}", self.config.name);
+        }
+        Ok(responses)
+    }
+
+    /// Send a response to everyone in the campaign, recording it in the session log.
+    pub fn broadcast(&self, response: SystemResponse) {
+        self.log.lock().unwrap().record(history::now(), &response);
+        let _ = self.tx.send(response);
+    }
+
+    /// Get the full state of the campaign, for a client that missed some messages.
+    pub fn resync(&self, missed: u64) -> SystemResponse {
+        let (settings, scene, heroes) = {
+            let room = self.room.lock().unwrap();
+            (room.settings.clone(), room.scene.clone(), room.heroes.clone())
+        };
+        let members = self.connections.lock().unwrap().roster();
+        SystemResponse::Resync { settings, scene, heroes, members, missed }
+    }
+
+    /// Get the role a passphrase lets someone join as, if it's one of the campaign's passphrases.
+    pub fn role_for(&self, passphrase: &str) -> Option<&'static str> {
+        if passphrase == self.config.narrator_passphrase {
+            Some("narrator")
+        } else if passphrase == self.config.passphrase {
+            Some("player")
+        } else {
+            None
+        }
+    }
+}
+
+/// Load the campaigns from a TOML file, keyed by name, each with its room rebuilt from its event log.
+/// No one can join if the file can't be read.
+pub fn load_campaigns(path: &Path, events_dir: &Path) -> HashMap<String, Arc<Campaign>> {
+    let file = std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|content| toml::from_str::<CampaignsFile>(&content).map_err(|err| err.to_string()));
+    let configs = match file {
+        Ok(file) => file.campaigns,
+        Err(err) => {
+            tracing::warn!("Couldn't load campaigns from {}: {err}", path.display());
+            return HashMap::new();
+        }
+    };
+    tracing::info!("Loaded {} campaigns from {}", configs.len(), path.display());
+    configs.into_iter().map(|config| (config.name.clone(), Arc::new(Campaign::new(config, events_dir)))).collect()
+}
@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use shared::{messaging::SystemResponse, presence::Member};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+
+/// How often connections are pinged to check they're still there.
+pub const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// How long a connection can go without being heard from, even by a pong, before it counts as idle.
+const IDLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Who should receive a response.
+pub enum Audience {
+    Everyone,
+    /// Only these users, and whoever is narrating if `narrator` is set. Usernames are never matched against roles.
+    Only { usernames: Vec<String>, narrator: bool },
+}
+
+impl Audience {
+    /// Work out who a response is meant for. Whispers go to the sender and the recipient, who can be
+    /// "narrator" to whisper to whoever is narrating, and secret rolls go to the roller and the narrator;
+    /// everything else goes to everyone.
+    pub fn of(response: &SystemResponse) -> Self {
+        match response {
+            SystemResponse::Whisper { from, to, .. } if to == "narrator" => {
+                Audience::Only { usernames: vec![from.clone()], narrator: true }
+            }
+            SystemResponse::Whisper { from, to, .. } => Audience::Only { usernames: vec![from.clone(), to.clone()], narrator: false },
+            SystemResponse::SecretRoll { username, .. } => Audience::Only { usernames: vec![username.clone()], narrator: true },
+            _ => Audience::Everyone,
+        }
+    }
+}
+
+/// A connected client and the user its session belongs to.
+struct Connection {
+    username: String,
+    role: String,
+    tx: UnboundedSender<SystemResponse>,
+    last_seen: Instant,
+    is_idle: bool,
+    /// Dropped to close the connection.
+    _close: oneshot::Sender<()>,
+}
+
+impl Connection {
+    fn is_in(&self, usernames: &[String], narrator: bool) -> bool {
+        usernames.contains(&self.username) || (narrator && self.role == "narrator")
+    }
+}
+
+/// The open connections, so responses can be sent to particular users rather than broadcast.
+#[derive(Default)]
+pub struct Connections {
+    connections: HashMap<u64, Connection>,
+    next_id: u64,
+}
+
+impl Connections {
+    /// Add a connection for a user, returning its id and a receiver that resolves if the connection is closed by the server.
+    pub fn add(&mut self, username: String, role: String, tx: UnboundedSender<SystemResponse>) -> (u64, oneshot::Receiver<()>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let (close, closed) = oneshot::channel();
+        self.connections.insert(id, Connection { username, role, tx, last_seen: Instant::now(), is_idle: false, _close: close });
+        (id, closed)
+    }
+
+    /// Note that a connection has been heard from. Returns whether it was idle until now.
+    pub fn seen(&mut self, id: u64) -> bool {
+        let Some(connection) = self.connections.get_mut(&id) else {
+            return false;
+        };
+        connection.last_seen = Instant::now();
+        std::mem::replace(&mut connection.is_idle, false)
+    }
+
+    /// Mark a connection idle if it hasn't been heard from for a while. Returns whether it just became idle.
+    pub fn check_idle(&mut self, id: u64) -> bool {
+        let Some(connection) = self.connections.get_mut(&id) else {
+            return false;
+        };
+        if connection.is_idle || connection.last_seen.elapsed() < IDLE_AFTER {
+            return false;
+        }
+        connection.is_idle = true;
+        true
+    }
+
+    /// Get everyone connected, once each, ordered by name.
+    pub fn roster(&self) -> Vec<Member> {
+        let mut members: Vec<Member> = vec![];
+        for connection in self.connections.values() {
+            match members.iter_mut().find(|member| member.username == connection.username) {
+                Some(member) => member.is_idle &= connection.is_idle,
+                None => members.push(Member {
+                    username: connection.username.clone(),
+                    role: connection.role.clone(),
+                    is_idle: connection.is_idle,
+                }),
+            }
+        }
+        members.sort_by(|a, b| a.username.cmp(&b.username));
+        members
+    }
+
+    /// Whether a user has a connection open.
+    pub fn is_connected(&self, username: &str) -> bool {
+        self.connections.values().any(|connection| connection.username == username)
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.connections.remove(&id);
+    }
+
+    /// Close every connection a user has open, as when their session is revoked.
+    pub fn disconnect(&mut self, username: &str) {
+        self.connections.retain(|_, connection| connection.username != username);
+    }
+
+    /// Send a response to every connection of the given users, and of the narrator if `narrator` is set.
+    pub fn send_to(&self, usernames: &[String], narrator: bool, response: &SystemResponse) {
+        for connection in self.connections.values().filter(|connection| connection.is_in(usernames, narrator)) {
+            let _ = connection.tx.send(response.clone());
+        }
+    }
+}
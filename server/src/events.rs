@@ -0,0 +1,158 @@
+use std::{
+    fs::{self, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use shared::messaging::SystemResponse;
+use tokio::{fs::File, io::AsyncWriteExt, sync::mpsc};
+
+use crate::{history, room::Room};
+
+/// The directory campaigns' event logs are kept in when `EVENTS_DIR` isn't set.
+pub const DEFAULT_EVENTS_DIR: &str = "events";
+
+/// How many batches of events are written between snapshots of the room.
+const SNAPSHOT_EVERY: u64 = 100;
+
+const EVENTS_FILE: &str = "events.jsonl";
+const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// The events the room sent out for one request, in the order they were sent.
+#[derive(Deserialize, Serialize)]
+pub struct EventBatch {
+    pub seq: u64,
+    /// When the request was handled, in seconds since the Unix epoch.
+    pub at: u64,
+    pub events: Vec<SystemResponse>,
+}
+
+/// The room as it was after a batch of events, so it can be rebuilt without replaying the whole log.
+#[derive(Deserialize, Serialize)]
+struct Snapshot {
+    seq: u64,
+    room: Room,
+}
+
+/// Something to write to the event log, in the order it was handed to the writer.
+enum Write {
+    Batch(String),
+    Snapshot(String),
+}
+
+/// An append-only log of a campaign's events, one batch per line, with a snapshot of the room every so often.
+/// Batches are numbered and serialized as they're appended, then written out in order by a separate task,
+/// so appending never waits on the disk.
+pub struct EventStore {
+    dir: PathBuf,
+    writer: mpsc::UnboundedSender<Write>,
+    next_seq: u64,
+}
+
+impl EventStore {
+    /// Open the event log in a directory, rebuilding the room from the latest snapshot and the events after it.
+    pub fn open(dir: &Path) -> io::Result<(Self, Room)> {
+        fs::create_dir_all(dir)?;
+        let (mut next_seq, mut room) = match read_snapshot(dir)? {
+            Some(snapshot) => (snapshot.seq + 1, snapshot.room),
+            None => (0, Room::default()),
+        };
+        let snapshot_seq = next_seq;
+        let (batches, unfinished) = read_batches(dir)?;
+        if let Some(complete) = unfinished {
+            tracing::warn!("Dropping an unfinished event from {}", dir.join(EVENTS_FILE).display());
+            OpenOptions::new().write(true).open(dir.join(EVENTS_FILE))?.set_len(complete)?;
+        }
+        for batch in batches.into_iter().filter(|batch| batch.seq >= snapshot_seq) {
+            room.replay(&batch.events);
+            next_seq = batch.seq + 1;
+        }
+        tracing::info!("Rebuilt the room in {} by replaying {} batches of events", dir.display(), next_seq - snapshot_seq);
+        let file = OpenOptions::new().create(true).append(true).open(dir.join(EVENTS_FILE))?;
+        let (writer, writes) = mpsc::unbounded_channel();
+        tokio::spawn(write_events(dir.to_path_buf(), File::from_std(file), writes));
+        Ok((EventStore { dir: dir.to_path_buf(), writer, next_seq }, room))
+    }
+
+    /// Append the events from one request, taking a snapshot of the room they left if one is due.
+    pub fn append(&mut self, events: Vec<SystemResponse>, room: &Room) -> serde_json::Result<()> {
+        let batch = EventBatch { seq: self.next_seq, at: history::now(), events };
+        let mut line = serde_json::to_string(&batch)?;
+        line.push('\n');
+        let _ = self.writer.send(Write::Batch(line));
+        self.next_seq += 1;
+        if self.next_seq.is_multiple_of(SNAPSHOT_EVERY) {
+            let snapshot = serde_json::to_string(&Snapshot { seq: batch.seq, room: room.clone() })?;
+            let _ = self.writer.send(Write::Snapshot(snapshot));
+        }
+        Ok(())
+    }
+
+    /// Get the directory the log is kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// Rebuild the room from the start of the log in a directory, up to and including the given batch, to see how things stood then.
+/// A batch still being written is left out.
+pub fn replay(dir: &Path, upto: u64) -> io::Result<Room> {
+    let mut room = Room::default();
+    for batch in read_batches(dir)?.0.into_iter().take_while(|batch| batch.seq <= upto) {
+        room.replay(&batch.events);
+    }
+    Ok(room)
+}
+
+/// Write batches and snapshots to the log as they're appended, until the store is dropped.
+async fn write_events(dir: PathBuf, mut file: File, mut writes: mpsc::UnboundedReceiver<Write>) {
+    while let Some(write) = writes.recv().await {
+        let result = match write {
+            Write::Batch(line) => match file.write_all(line.as_bytes()).await {
+                Ok(()) => file.flush().await,
+                Err(err) => Err(err),
+            },
+            Write::Snapshot(snapshot) => write_snapshot(&dir, snapshot).await,
+        };
+        if let Err(err) = result {
+            tracing::error!("Couldn't write to the event log in {}: {err}", dir.display());
+        }
+    }
+}
+
+fn read_snapshot(dir: &Path) -> io::Result<Option<Snapshot>> {
+    match fs::read_to_string(dir.join(SNAPSHOT_FILE)) {
+        Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Write a snapshot to a temporary file first, so a crash part way through leaves the last one intact.
+async fn write_snapshot(dir: &Path, snapshot: String) -> io::Result<()> {
+    let temporary = dir.join(format!("{SNAPSHOT_FILE}.tmp"));
+    tokio::fs::write(&temporary, snapshot).await?;
+    tokio::fs::rename(temporary, dir.join(SNAPSHOT_FILE)).await
+}
+
+/// Read every complete batch in the log, along with the length of the complete part if the last line is
+/// unfinished, as when cut short by a crash. Any other line that can't be read is an error, since replaying
+/// around it would give the wrong room.
+fn read_batches(dir: &Path) -> io::Result<(Vec<EventBatch>, Option<u64>)> {
+    let content = match fs::read_to_string(dir.join(EVENTS_FILE)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((vec![], None)),
+        Err(err) => return Err(err),
+    };
+    let mut batches = vec![];
+    let mut complete = 0;
+    for line in content.split_inclusive('\n') {
+        if !line.ends_with('\n') {
+            return Ok((batches, Some(complete as u64)));
+        }
+        batches.push(serde_json::from_str(line)?);
+        complete += line.len();
+    }
+    Ok((batches, None))
+}
@@ -0,0 +1,102 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use shared::history::AuditEntry;
+
+use crate::room::RoomError;
+
+/// How many of the latest actions can be undone, and are shown in the history.
+pub const MAX_UNDO: usize = 20;
+
+/// Get the current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or_default()
+}
+
+/// The latest actions taken in a room, with snapshots of the state around them so they can be undone and redone.
+/// The full audit trail is kept in the campaign's event log, so only as many entries are kept here as can be undone.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct History<S> {
+    /// Actions that can be undone, latest last, with the state from before each.
+    done: Vec<(AuditEntry, S)>,
+    /// Actions that have been undone and can be redone, latest last, with the state from after each.
+    undone: Vec<(AuditEntry, S)>,
+    /// The latest entries in the audit trail, latest last.
+    audit: Vec<AuditEntry>,
+    next_id: u64,
+}
+
+impl<S> Default for History<S> {
+    fn default() -> Self {
+        History { done: vec![], undone: vec![], audit: vec![], next_id: 0 }
+    }
+}
+
+impl<S> History<S> {
+    /// Record an action along with the state from before it. Anything undone can no longer be redone.
+    pub fn record(&mut self, username: &str, role: &str, action: String, narrator_only: bool, before: S) -> AuditEntry {
+        let entry = AuditEntry {
+            id: self.next_id,
+            at: now(),
+            username: username.to_string(),
+            role: role.to_string(),
+            action,
+            undone: false,
+            narrator_only,
+        };
+        self.push(entry.clone(), before);
+        entry
+    }
+
+    /// Add an action that has already been given its entry, as when replaying the event log.
+    pub fn push(&mut self, entry: AuditEntry, before: S) {
+        self.next_id = entry.id + 1;
+        self.audit.push(entry.clone());
+        if self.audit.len() > MAX_UNDO {
+            self.audit.remove(0);
+        }
+        self.done.push((entry, before));
+        if self.done.len() > MAX_UNDO {
+            self.done.remove(0);
+        }
+        self.undone.clear();
+    }
+
+    /// Undo the latest action, given the current state, returning it and the state to go back to.
+    pub fn undo(&mut self, username: &str, role: &str, current: S) -> Result<(AuditEntry, S), RoomError> {
+        let (entry, _) = self.done.last().ok_or(RoomError::NothingToUndo)?;
+        if !entry.can_be_undone_by(username, role) {
+            return Err(RoomError::NotYourAction(entry.clone()));
+        }
+        let (entry, before) = self.done.pop().ok_or(RoomError::NothingToUndo)?;
+        let entry = self.mark(entry, true);
+        self.undone.push((entry.clone(), current));
+        Ok((entry, before))
+    }
+
+    /// Redo the latest undone action, given the current state, returning it and the state it left behind.
+    pub fn redo(&mut self, username: &str, role: &str, current: S) -> Result<(AuditEntry, S), RoomError> {
+        let (entry, _) = self.undone.last().ok_or(RoomError::NothingToRedo)?;
+        if !entry.can_be_undone_by(username, role) {
+            return Err(RoomError::NotYourAction(entry.clone()));
+        }
+        let (entry, after) = self.undone.pop().ok_or(RoomError::NothingToRedo)?;
+        let entry = self.mark(entry, false);
+        self.done.push((entry.clone(), current));
+        Ok((entry, after))
+    }
+
+    /// Mark an action as undone or redone in the audit trail.
+    fn mark(&mut self, mut entry: AuditEntry, undone: bool) -> AuditEntry {
+        entry.undone = undone;
+        if let Some(audited) = self.audit.iter_mut().find(|audited| audited.id == entry.id) {
+            audited.undone = undone;
+        }
+        entry
+    }
+
+    /// Get the latest entries in the audit trail.
+    pub fn recent(&self) -> Vec<AuditEntry> {
+        self.audit.clone()
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use shared::messaging::{LimitExceeded, SystemRequest};
+
+/// The largest message the socket will read at all, whatever a campaign's limits are.
+pub const MAX_SOCKET_MESSAGE_BYTES: usize = 1024 * 1024;
+
+/// How long rate limits count requests over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// What each connection to a campaign may send, set per campaign in the campaigns file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub max_message_bytes: usize,
+    pub max_content_chars: usize,
+    pub chats_per_minute: usize,
+    pub rolls_per_minute: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits { max_message_bytes: 64 * 1024, max_content_chars: 2000, chats_per_minute: 30, rolls_per_minute: 12 }
+    }
+}
+
+/// Counts requests over the last minute, refusing any beyond the maximum.
+struct RateLimiter {
+    max: usize,
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    fn new(max: usize) -> Self {
+        RateLimiter { max, sent: VecDeque::new() }
+    }
+
+    /// Record a request, or return how long until another is allowed.
+    fn check(&mut self, now: Instant) -> Result<(), Duration> {
+        while self.sent.front().is_some_and(|sent| now.duration_since(*sent) >= RATE_WINDOW) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= self.max {
+            let oldest = self.sent.front().copied().unwrap_or(now);
+            return Err(RATE_WINDOW.saturating_sub(now.duration_since(oldest)));
+        }
+        self.sent.push_back(now);
+        Ok(())
+    }
+}
+
+/// The limits for a single connection, along with what it has sent recently.
+pub struct ConnectionLimits {
+    limits: Limits,
+    chats: RateLimiter,
+    rolls: RateLimiter,
+}
+
+impl ConnectionLimits {
+    pub fn new(limits: Limits) -> Self {
+        ConnectionLimits {
+            chats: RateLimiter::new(limits.chats_per_minute),
+            rolls: RateLimiter::new(limits.rolls_per_minute),
+            limits,
+        }
+    }
+
+    /// Check the size of a message before it's parsed.
+    pub fn check_size(&self, size: usize) -> Result<(), LimitExceeded> {
+        if size > self.limits.max_message_bytes {
+            return Err(LimitExceeded::MessageTooLarge { size, max: self.limits.max_message_bytes });
+        }
+        Ok(())
+    }
+
+    /// Check a request's content length and rate, counting it towards the rate if it's allowed.
+    pub fn check_request(&mut self, request: &SystemRequest) -> Result<(), LimitExceeded> {
+        let now = Instant::now();
+        match request {
+            SystemRequest::Chat { content, .. } | SystemRequest::Emote { content, .. } | SystemRequest::Whisper { content, .. } => {
+                let length = content.chars().count();
+                if length > self.limits.max_content_chars {
+                    return Err(LimitExceeded::ContentTooLong { length, max: self.limits.max_content_chars });
+                }
+                self.chats
+                    .check(now)
+                    .map_err(|wait| LimitExceeded::TooManyChats { retry_after_ms: wait.as_millis() as u64 })
+            }
+            SystemRequest::Roll { .. } | SystemRequest::SecretRoll { .. } | SystemRequest::ReactionRoll { .. } => self
+                .rolls
+                .check(now)
+                .map_err(|wait| LimitExceeded::TooManyRolls { retry_after_ms: wait.as_millis() as u64 }),
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,110 +1,379 @@
+mod auth;
+mod campaigns;
+mod connections;
+mod events;
+mod history;
+mod limits;
+mod metrics;
+mod roll;
+mod stats;
+mod room;
+mod themebooks;
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
 use axum::{
-    Router,
+    Json, Router,
     extract::{
-        State, WebSocketUpgrade,
+        Query, State, WebSocketUpgrade,
         ws::{Message, WebSocket},
     },
-    http::HeaderValue,
-    response::IntoResponse,
-    routing::get,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use auth::{Session, Sessions};
+use campaigns::Campaign;
+use connections::{Audience, PING_INTERVAL};
+use limits::{ConnectionLimits, MAX_SOCKET_MESSAGE_BYTES};
+use room::RoomError;
+use futures_util::{
+    SinkExt, StreamExt,
+    stream::{SplitSink, SplitStream},
+};
+use serde::Deserialize;
+use shared::{
+    log::LogFormat,
+    messaging::{LoginRequest, LoginResponse, SystemRequest, SystemResponse},
+    themebook::Themebook,
+};
+use tokio::sync::{
+    broadcast::{Receiver, error::RecvError},
+    mpsc,
 };
-use futures_util::{SinkExt, StreamExt};
-use shared::{messaging::{SystemRequest, SystemResponse}, modifier::{ModifierMap, Polarity}};
-use tokio::sync::broadcast::{Receiver, Sender, channel};
 use tower_http::cors::{Any, CorsLayer};
 
+#[derive(Clone)]
+struct AppState {
+    campaigns: Arc<HashMap<String, Arc<Campaign>>>,
+    sessions: Arc<Mutex<Sessions>>,
+    themebooks: Arc<Vec<Themebook>>,
+}
+
+impl AppState {
+    /// Get the session a token was issued for, and the campaign it's in.
+    fn session(&self, token: &str) -> Option<(Session, Arc<Campaign>)> {
+        let session = self.sessions.lock().unwrap().get(token)?;
+        let campaign = self.campaigns.get(&session.campaign)?.clone();
+        Some((session, campaign))
+    }
+}
+
+/// The query string the WebSocket is opened with, and other requests that only need a session token.
+#[derive(Deserialize)]
+struct MessageQuery {
+    #[serde(default)]
+    token: String,
+}
+
+/// The query string a session log is downloaded with.
+#[derive(Deserialize)]
+struct LogQuery {
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    format: LogFormat,
+}
+
+/// The query string the narrator replays the event log with.
+#[derive(Deserialize)]
+struct ReplayQuery {
+    #[serde(default)]
+    token: String,
+    /// The last batch of events to replay.
+    upto: u64,
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
-    let (tx, _) = channel(100);
-    let app = app(tx);
+    let themebook_dir = std::env::var("THEMEBOOK_DIR").unwrap_or_else(|_| themebooks::DEFAULT_THEMEBOOK_DIR.into());
+    let themebooks = Arc::new(themebooks::load_themebooks(Path::new(&themebook_dir)));
+    let campaigns_file = std::env::var("CAMPAIGNS_FILE").unwrap_or_else(|_| campaigns::DEFAULT_CAMPAIGNS_FILE.into());
+    let events_dir = std::env::var("EVENTS_DIR").unwrap_or_else(|_| events::DEFAULT_EVENTS_DIR.into());
+    let campaigns = Arc::new(campaigns::load_campaigns(Path::new(&campaigns_file), Path::new(&events_dir)));
+    let app = app(AppState { campaigns, sessions: Arc::default(), themebooks });
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
-fn app(tx: Sender<SystemResponse>) -> Router {
+fn app(state: AppState) -> Router {
     let origins = vec!["http://127.0.0.1:8080".parse::<HeaderValue>().unwrap()];
-    let cors_layer = CorsLayer::new().allow_origin(origins).allow_methods(Any);
+    let cors_layer = CorsLayer::new().allow_origin(origins).allow_methods(Any).allow_headers(Any);
 
     Router::new()
         .route("/", get(|| async { "Hello, World!" }))
+        .route("/login", post(login_handler))
+        .route("/logout", post(logout_handler))
         .route("/message", get(message_handler))
-        .with_state(tx)
+        .route("/themebooks", get(themebooks_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/log", get(log_handler))
+        .route("/replay", get(replay_handler))
+        .route("/stats", get(stats_handler))
+        .with_state(state)
         .layer(cors_layer)
 }
 
+async fn themebooks_handler(State(state): State<AppState>) -> Json<Vec<Themebook>> {
+    Json(state.themebooks.as_ref().clone())
+}
+
+async fn login_handler(
+    State(state): State<AppState>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, (StatusCode, String)> {
+    let campaign = state.campaigns.get(&request.campaign).map(Arc::as_ref);
+    match state.sessions.lock().unwrap().login(campaign, request) {
+        Ok(response) => Ok(Json(response)),
+        Err(err) => {
+            tracing::warn!("Refused login: {err}");
+            Err((StatusCode::UNAUTHORIZED, err.to_string()))
+        }
+    }
+}
+
+/// End the token's session, so it can't be used again and its name is free.
+async fn logout_handler(Query(query): Query<MessageQuery>, State(state): State<AppState>) -> Response {
+    let Some(session) = state.sessions.lock().unwrap().revoke(&query.token) else {
+        return (StatusCode::UNAUTHORIZED, "that session has already ended").into_response();
+    };
+    tracing::info!("{} logged out of {}", session.username, session.campaign);
+    if let Some(campaign) = state.campaigns.get(&session.campaign) {
+        campaign.connections.lock().unwrap().disconnect(&session.username);
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Upgrade to a WebSocket for the campaign the session token was issued for, refusing unknown tokens.
 async fn message_handler(
     ws: WebSocketUpgrade,
-    State(tx): State<Sender<SystemResponse>>,
+    Query(query): Query<MessageQuery>,
+    State(state): State<AppState>,
 ) -> impl IntoResponse {
-    tracing::info!("Upgrading to WebSocket");
-    ws.on_upgrade(|socket| handle_socket(socket, tx))
+    let Some((session, campaign)) = state.session(&query.token) else {
+        tracing::warn!("Refused WebSocket with an unknown session token");
+        return (StatusCode::UNAUTHORIZED, "log in before connecting").into_response();
+    };
+    tracing::info!("Upgrading to WebSocket for {} in {}", session.username, session.campaign);
+    ws.max_message_size(MAX_SOCKET_MESSAGE_BYTES)
+        .max_frame_size(MAX_SOCKET_MESSAGE_BYTES)
+        .on_upgrade(move |socket| async move {
+            state.sessions.lock().unwrap().connected(&query.token);
+            handle_socket(socket, session, campaign).await;
+            state.sessions.lock().unwrap().disconnected(&query.token);
+        })
 }
 
-async fn handle_socket(socket: WebSocket, tx: Sender<SystemResponse>) {
-    let (mut sender, mut receiver) = socket.split();
-    let mut rx: Receiver<SystemResponse> = tx.subscribe();
+async fn metrics_handler(State(state): State<AppState>) -> String {
+    let mut campaigns: Vec<_> = state.campaigns.values().collect();
+    campaigns.sort_by(|a, b| a.config.name.cmp(&b.config.name));
+    campaigns.iter().map(|campaign| campaign.metrics.render(&campaign.config.name)).collect()
+}
 
-    tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            tracing::info!("Sending message: {:?}", msg);
-            sender
-                .send(Message::from(serde_json::to_string(&msg).unwrap()))
-                .await
-                .unwrap();
+/// Download the session log of the token's campaign, leaving out anything hidden from its role.
+async fn log_handler(Query(query): Query<LogQuery>, State(state): State<AppState>) -> Response {
+    let Some((session, campaign)) = state.session(&query.token) else {
+        return (StatusCode::UNAUTHORIZED, "log in before downloading the log").into_response();
+    };
+    let log = campaign.log.lock().unwrap().visible_to(&session.role);
+    match log.export(query.format) {
+        Ok(content) => {
+            let disposition = format!("attachment; filename=\"{}-log.{}\"", session.campaign, query.format.extension());
+            ([(header::CONTENT_TYPE, query.format.mime().to_string()), (header::CONTENT_DISPOSITION, disposition)], content)
+                .into_response()
+        }
+        Err(err) => {
+            tracing::error!("Couldn't export the log of {}: {err}", session.campaign);
+            (StatusCode::INTERNAL_SERVER_ERROR, "couldn't export the log").into_response()
         }
-    });
+    }
+}
 
-    while let Some(msg) = receiver.next().await {
-        if let Ok(msg) = msg {
-            match msg {
-                Message::Text(content) => {
-                    let msg: SystemRequest = serde_json::from_str(&content).unwrap();
-                    tracing::info!("Received message: {:?}", msg);
-                    match msg {
-                        SystemRequest::Chat {
-                            username,
-                            content,
-                            role,
-                        } => {
-                            let response = SystemResponse::Chat {
-                                username,
-                                content,
-                                role,
-                            };
-                            tx.send(response).unwrap();
-                        }
-                        SystemRequest::Roll { username, modifiers } => {
-                            let roll = (
-                                (rand::random::<u8>() % 6 + 1) as i8,
-                                (rand::random::<u8>() % 6 + 1) as i8,
-                            );
-                            let roll_total = roll.0 + roll.1;
-                            let total = apply_mods_to_roll(roll_total, &modifiers);
-                            let response = SystemResponse::Roll {
-                                dice_values: roll,
-                                username,
-                                modifiers,
-                                total,
-                            };
-                            tx.send(response).unwrap();
-                        }
-                        _ => (),
-                    }
+/// Get the roll statistics of the token's campaign.
+async fn stats_handler(Query(query): Query<MessageQuery>, State(state): State<AppState>) -> Response {
+    let Some((_, campaign)) = state.session(&query.token) else {
+        return (StatusCode::UNAUTHORIZED, "log in before looking at the statistics").into_response();
+    };
+    let report = campaign.stats.lock().unwrap().report();
+    Json(report).into_response()
+}
+
+/// Rebuild the room of the narrator's campaign from its event log as it stood after a given batch of events.
+async fn replay_handler(Query(query): Query<ReplayQuery>, State(state): State<AppState>) -> Response {
+    let Some((session, campaign)) = state.session(&query.token) else {
+        return (StatusCode::UNAUTHORIZED, "log in before replaying the campaign").into_response();
+    };
+    if session.role != "narrator" {
+        return (StatusCode::FORBIDDEN, "only the narrator can replay the campaign").into_response();
+    }
+    let Some(events) = &campaign.events else {
+        return (StatusCode::NOT_FOUND, "this campaign has no event log").into_response();
+    };
+    let dir = events.lock().unwrap().dir().to_path_buf();
+    let room = tokio::task::spawn_blocking(move || events::replay(&dir, query.upto)).await.unwrap_or_else(|err| Err(std::io::Error::other(err)));
+    match room {
+        Ok(room) => Json(SystemResponse::StateRestored { settings: room.settings, scene: room.scene, heroes: room.heroes }).into_response(),
+        Err(err) => {
+            tracing::error!("Couldn't replay the event log of {}: {err}", session.campaign);
+            (StatusCode::INTERNAL_SERVER_ERROR, "couldn't replay the event log").into_response()
+        }
+    }
+}
+
+async fn handle_socket(socket: WebSocket, session: Session, campaign: Arc<Campaign>) {
+    let (sender, receiver) = socket.split();
+    let rx = campaign.tx.subscribe();
+    let (direct_tx, direct_rx) = mpsc::unbounded_channel::<SystemResponse>(); // responses for this connection only
+    let (connection_id, closed) = campaign.connections.lock().unwrap().add(session.username.clone(), session.role.clone(), direct_tx.clone());
+    let roster = campaign.connections.lock().unwrap().roster();
+    let _ = direct_tx.send(SystemResponse::Roster { members: roster });
+    let _ = direct_tx.send(SystemResponse::History { entries: campaign.room.lock().unwrap().recent_history() });
+    let _ = campaign.tx.send(SystemResponse::Joined { username: session.username.clone(), role: session.role.clone() });
+    let _ = campaign.tx.send(campaign.room.lock().unwrap().commit(&session.username));
+
+    let mut send_task = tokio::spawn(forward_responses(sender, rx, direct_rx, session.clone(), campaign.clone(), connection_id));
+    let mut receive_task = tokio::spawn(receive_requests(receiver, direct_tx, session.clone(), campaign.clone(), connection_id));
+    // when either half of the socket stops, stop the other too, so a dead connection doesn't linger
+    tokio::select! {
+        _ = &mut send_task => receive_task.abort(),
+        _ = &mut receive_task => send_task.abort(),
+        _ = closed => {
+            send_task.abort();
+            receive_task.abort();
+        }
+    }
+
+    let mut connections = campaign.connections.lock().unwrap();
+    connections.remove(connection_id);
+    if !connections.is_connected(&session.username) {
+        let _ = campaign.tx.send(SystemResponse::Left { username: session.username });
+    }
+}
+
+/// Send broadcast and direct responses to the client, pinging it now and then, until the socket fails.
+/// A client that falls too far behind the broadcast is sent the full state of the campaign instead of what it missed.
+async fn forward_responses(
+    mut sender: SplitSink<WebSocket, Message>,
+    mut rx: Receiver<SystemResponse>,
+    mut direct_rx: mpsc::UnboundedReceiver<SystemResponse>,
+    session: Session,
+    campaign: Arc<Campaign>,
+    connection_id: u64,
+) {
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    loop {
+        let msg = tokio::select! {
+            received = rx.recv() => match received {
+                Ok(msg) => msg,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("{} fell {missed} messages behind, resyncing", session.username);
+                    campaign.metrics.record_lag(missed);
+                    campaign.resync(missed)
                 }
-                Message::Close(_) => break,
-                _ => (),
+                Err(RecvError::Closed) => break,
+            },
+            Some(msg) = direct_rx.recv() => msg,
+            _ = ping.tick() => {
+                if campaign.connections.lock().unwrap().check_idle(connection_id) {
+                    let _ = campaign.tx.send(SystemResponse::Idle { username: session.username.clone(), is_idle: true });
+                }
+                if sender.send(Message::Ping(Default::default())).await.is_err() {
+                    campaign.metrics.record_failed_send();
+                    break;
+                }
+                continue;
             }
+        };
+        let Some(msg) = msg.visible_to(&session.role) else {
+            continue;
+        };
+        tracing::info!("Sending message: {:?}", msg);
+        let json = match serde_json::to_string(&msg) {
+            Ok(json) => json,
+            Err(err) => {
+                tracing::error!("Couldn't serialize {msg:?}: {err}");
+                continue;
+            }
+        };
+        if let Err(err) = sender.send(Message::from(json)).await {
+            tracing::info!("Stopped sending to {}: {err}", session.username);
+            campaign.metrics.record_failed_send();
+            break;
         }
     }
 }
 
-fn apply_mods_to_roll(mut roll_total: i8, mods: &ModifierMap) -> i8 {
-    for (modifier, polarity, is_burned) in mods.get_modifiers() {
-        match polarity {
-            Polarity::Positive => roll_total += (if is_burned { modifier.get_value() * 3 } else { modifier.get_value() }) as i8, // TODO make sure only power/story tags can be burned
-            Polarity::Negative => roll_total -= modifier.get_value() as i8,
+/// Handle requests from the client until it closes the socket or the socket fails.
+async fn receive_requests(
+    mut receiver: SplitStream<WebSocket>,
+    direct_tx: mpsc::UnboundedSender<SystemResponse>,
+    session: Session,
+    campaign: Arc<Campaign>,
+    connection_id: u64,
+) {
+    let mut limits = ConnectionLimits::new(campaign.config.limits.clone());
+    while let Some(msg) = receiver.next().await {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(err) => {
+                tracing::info!("Stopped receiving from {}: {err}", session.username);
+                break;
+            }
+        };
+        if campaign.connections.lock().unwrap().seen(connection_id) {
+            let _ = campaign.tx.send(SystemResponse::Idle { username: session.username.clone(), is_idle: false });
+        }
+        match msg {
+            Message::Text(content) => {
+                if let Err(limit) = limits.check_size(content.len()) {
+                    tracing::warn!("{} broke a limit: {limit}", session.username);
+                    let _ = direct_tx.send(SystemResponse::LimitExceeded { limit });
+                    continue;
+                }
+                let mut msg: SystemRequest = match serde_json::from_str(&content) {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        tracing::warn!("Couldn't parse request from {}: {err}", session.username);
+                        let _ = direct_tx.send(SystemResponse::Error { message: format!("couldn't read that request: {err}") });
+                        continue;
+                    }
+                };
+                msg.authenticate(&session.username, &session.role);
+                tracing::info!("Received message: {:?}", msg);
+                if let Err(limit) = limits.check_request(&msg) {
+                    tracing::warn!("{} broke a limit: {limit}", session.username);
+                    let _ = direct_tx.send(SystemResponse::LimitExceeded { limit });
+                    continue;
+                }
+                let result = campaign.handle(&session.username, &session.role, msg);
+                match result {
+                    Ok(responses) => {
+                        for response in responses {
+                            match Audience::of(&response) {
+                                Audience::Everyone => campaign.broadcast(response),
+                                Audience::Only { usernames, narrator } => {
+                                    campaign.connections.lock().unwrap().send_to(&usernames, narrator, &response)
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        tracing::warn!("Rejected request: {err}");
+                        if matches!(err, RoomError::NoCommitment) {
+                            // commit to a seed now, so the roll can be made again fairly
+                            let _ = campaign.tx.send(campaign.room.lock().unwrap().commit(&session.username));
+                        }
+                        let _ = direct_tx.send(SystemResponse::Error { message: err.to_string() });
+                    }
+                }
+            }
+            Message::Close(_) => break,
+            _ => (),
         }
     }
-    roll_total
 }
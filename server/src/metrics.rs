@@ -0,0 +1,34 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counts of messages a campaign's connections failed to get, for spotting slow or broken clients.
+#[derive(Default)]
+pub struct Metrics {
+    lagged_messages: AtomicU64,
+    resyncs: AtomicU64,
+    failed_sends: AtomicU64,
+}
+
+impl Metrics {
+    /// Record a connection falling behind the broadcast and missing messages.
+    pub fn record_lag(&self, missed: u64) {
+        self.lagged_messages.fetch_add(missed, Ordering::Relaxed);
+        self.resyncs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a message that couldn't be sent because the socket failed.
+    pub fn record_failed_send(&self) {
+        self.failed_sends.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Write the metrics in the Prometheus text format, labelled with the campaign.
+    pub fn render(&self, campaign: &str) -> String {
+        [
+            ("lagged_messages_total", &self.lagged_messages),
+            ("resyncs_total", &self.resyncs),
+            ("failed_sends_total", &self.failed_sends),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{name}{{campaign=\"{campaign}\"}} {}\n", value.load(Ordering::Relaxed)))
+        .collect()
+    }
+}
@@ -0,0 +1,42 @@
+use shared::{
+    modifier::ModifierMap,
+    roll::{OutcomeDetails, OutcomeMode, RollOutcome},
+};
+
+/// Roll two six-sided dice.
+pub fn roll_dice() -> (i8, i8) {
+    (
+        (rand::random::<u8>() % 6 + 1) as i8,
+        (rand::random::<u8>() % 6 + 1) as i8,
+    )
+}
+
+pub fn apply_mods_to_roll(roll_total: i8, mods: &ModifierMap) -> i8 {
+    roll_total.saturating_add(mods.net_value())
+}
+
+/// Get the structured result of a roll for the given outcome mode.
+pub fn outcome_details(mode: OutcomeMode, total: i8, mods: &ModifierMap) -> OutcomeDetails {
+    let outcome = RollOutcome::from_total(total);
+    match mode {
+        OutcomeMode::Quick => OutcomeDetails::Quick { outcome },
+        OutcomeMode::Detailed => OutcomeDetails::Detailed { outcome, power: power_for_roll(total, mods) },
+    }
+}
+
+/// Get the power a roll yields to spend on effects: the net modifier (at least 1) on a hit, nothing on a miss.
+fn power_for_roll(total: i8, mods: &ModifierMap) -> u8 {
+    match RollOutcome::from_total(total) {
+        RollOutcome::Success | RollOutcome::Mixed => mods.net_value().max(1) as u8,
+        RollOutcome::Miss => 0,
+    }
+}
+
+/// Get how many tiers a reaction roll takes off an incoming status: the roll's power on a success, one tier on a mixed success.
+pub fn reaction_reduction(total: i8, mods: &ModifierMap) -> u8 {
+    match RollOutcome::from_total(total) {
+        RollOutcome::Success => mods.net_value().max(1) as u8,
+        RollOutcome::Mixed => 1,
+        RollOutcome::Miss => 0,
+    }
+}
@@ -0,0 +1,984 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use shared::{
+    camp::CampAction,
+    challenge::Challenge,
+    effect::Effect,
+    fairness::{self, RollProof},
+    hero::{BackpackAction, Hero, StatusAction},
+    history::AuditEntry,
+    limit::LimitOutcome,
+    messaging::{SystemRequest, SystemResponse},
+    modifier::{Modifier, ModifierMap, Polarity},
+    roll::{OutcomeDetails, OutcomeMode, RollOutcome},
+    scene::Scene,
+    settings::RoomSettings,
+    status::{Status, StatusError},
+    tag::{ExpiryReason, Tag, TagRef, TagScope},
+    visibility::{Secret, Visibility},
+};
+use uuid::Uuid;
+
+use crate::{
+    history::History,
+    roll::{apply_mods_to_roll, outcome_details, reaction_reduction, roll_dice},
+};
+
+/// The most effects power can be spent on at once. Rolls never yield anywhere near this much power.
+const MAX_EFFECTS: usize = 20;
+
+/// Reasons a request can be rejected by the room.
+#[derive(Debug)]
+pub enum RoomError {
+    NotNarrator,
+    UnknownRoll(u64),
+    NotYourRoll,
+    QuickOutcomes,
+    NotEnoughPower { cost: u16, remaining: u8 },
+    TooManyEffects(usize),
+    UnknownChallenge(String),
+    UnknownStatus(String),
+    NoStatuses(TagScope),
+    UnknownHero(String),
+    UnknownThreat(u64),
+    NoCamp,
+    AlreadyActed(String),
+    InvalidCampAction,
+    NotInBackpack,
+    NotInScene,
+    NotYourHero,
+    InvalidStatus(StatusError),
+    UnknownSecret,
+    NothingToUndo,
+    NothingToRedo,
+    NotYourAction(AuditEntry),
+    NoCommitment,
+    NoLimitMax { challenge: String, limit: String },
+    UnknownTag(TagScope),
+    CantBurn(String),
+}
+
+impl std::fmt::Display for RoomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoomError::NotNarrator => write!(f, "only the narrator can do that"),
+            RoomError::UnknownRoll(id) => write!(f, "roll {id} has no power left to spend"),
+            RoomError::NotYourRoll => write!(f, "you can only spend power from your own rolls"),
+            RoomError::QuickOutcomes => write!(f, "this table uses quick outcomes, so there is no power to spend"),
+            RoomError::NotEnoughPower { cost, remaining } => {
+                write!(f, "those effects cost {cost} power but only {remaining} remains")
+            }
+            RoomError::TooManyEffects(count) => write!(f, "can't spend power on {count} effects at once, the most is {MAX_EFFECTS}"),
+            RoomError::UnknownChallenge(name) => write!(f, "there is no challenge called {name}"),
+            RoomError::UnknownStatus(target) => write!(f, "{target} has no such status"),
+            RoomError::NoStatuses(scope) => write!(f, "{scope} can't have statuses"),
+            RoomError::UnknownHero(name) => write!(f, "there is no hero called {name}"),
+            RoomError::NotYourHero => write!(f, "you can only update your own hero"),
+            RoomError::UnknownThreat(id) => write!(f, "threat {id} has already been resolved"),
+            RoomError::NoCamp => write!(f, "the heroes aren't at camp"),
+            RoomError::AlreadyActed(hero) => write!(f, "{hero} has already taken a camp action"),
+            RoomError::InvalidCampAction => write!(f, "that camp action isn't possible for this hero"),
+            RoomError::NotInBackpack => write!(f, "that tag isn't in the backpack"),
+            RoomError::NotInScene => write!(f, "that tag isn't in the scene"),
+            RoomError::InvalidStatus(error) => write!(f, "{error}"),
+            RoomError::UnknownSecret => write!(f, "there's nothing like that to hide or reveal"),
+            RoomError::NothingToUndo => write!(f, "there's nothing to undo"),
+            RoomError::NothingToRedo => write!(f, "there's nothing to redo"),
+            RoomError::NoLimitMax { challenge, limit } => write!(f, "{challenge}'s {limit} limit has no maximum"),
+            RoomError::UnknownTag(scope) => write!(f, "{scope} has no such tag or theme"),
+            RoomError::CantBurn(name) => write!(f, "{name} can't be burned, only unscratched power and story tags and themes can"),
+            RoomError::NoCommitment => write!(f, "the server hasn't committed to a seed for your next roll yet, so roll again"),
+            RoomError::NotYourAction(entry) if entry.narrator_only => write!(f, "only the narrator can undo or redo \"{entry}\""),
+            RoomError::NotYourAction(entry) => write!(f, "only {} or the narrator can undo or redo \"{entry}\"", entry.username),
+        }
+    }
+}
+
+impl From<StatusError> for RoomError {
+    fn from(error: StatusError) -> Self {
+        RoomError::InvalidStatus(error)
+    }
+}
+
+/// Power from a roll that has not been spent yet.
+#[derive(Clone, Deserialize, Serialize)]
+struct PendingRoll {
+    username: String,
+    power: u8,
+}
+
+/// A status the narrator has threatened a hero with, waiting on the player's reaction.
+#[derive(Clone, Deserialize, Serialize)]
+struct PendingThreat {
+    hero: String,
+    status: String,
+    tier: u8,
+}
+
+/// A camp or downtime scene, tracking which heroes have taken their action.
+#[derive(Clone, Deserialize, Serialize)]
+struct Camp {
+    name: String,
+    acted: HashSet<String>,
+}
+
+/// The shared state of a game session.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Room {
+    pub settings: RoomSettings,
+    pub scene: Scene,
+    pub heroes: Vec<Hero>,
+    pending_rolls: HashMap<u64, PendingRoll>,
+    next_roll_id: u64,
+    pending_threats: HashMap<u64, PendingThreat>,
+    next_threat_id: u64,
+    camp: Option<Camp>,
+    history: History<Room>,
+    /// The seed each user's next fair roll will use, by username. Only their hashes are sent out until they're used.
+    #[serde(skip)]
+    seeds: HashMap<String, String>,
+}
+
+impl Room {
+    /// Handle a request from a user, returning the responses to broadcast.
+    /// Requests that change the room are recorded in its history so they can be undone.
+    pub fn handle(&mut self, username: &str, role: &str, request: SystemRequest) -> Result<Vec<SystemResponse>, RoomError> {
+        match request {
+            SystemRequest::Undo {} => self.undo(username, role),
+            SystemRequest::Redo {} => self.redo(username, role),
+            request => {
+                let Some(action) = request.audit_description() else {
+                    return self.apply(request);
+                };
+                let narrator_only = request.rolls_dice();
+                let before = self.snapshot();
+                let mut responses = self.apply(request)?;
+                let entry = self.history.record(username, role, action, narrator_only, before);
+                tracing::info!("{entry}");
+                responses.push(SystemResponse::History { entries: self.history.recent() });
+                Ok(responses)
+            }
+        }
+    }
+
+    /// Bring the room up to date with the events it sent out for one request, as when rebuilding it from the event log.
+    /// Undo and redo go back through the history, so the room and its history end up just as they were.
+    pub fn replay(&mut self, events: &[SystemResponse]) {
+        let result = match events.first() {
+            Some(SystemResponse::Undone { by, .. }) => self.undo(by, "narrator"),
+            Some(SystemResponse::Redone { by, .. }) => self.redo(by, "narrator"),
+            _ => {
+                let before = self.snapshot();
+                for event in events {
+                    self.apply_event(event);
+                }
+                if let Some(SystemResponse::History { entries }) = events.last()
+                    && let Some(entry) = entries.last()
+                {
+                    self.history.push(entry.clone(), before);
+                }
+                return;
+            }
+        };
+        if let Err(err) = result {
+            tracing::warn!("Couldn't replay {events:?}: {err}");
+        }
+    }
+
+    /// Apply the change an event announces to the room.
+    fn apply_event(&mut self, event: &SystemResponse) {
+        match event {
+            SystemResponse::SceneUpdate { scene } => self.scene = scene.clone(),
+            SystemResponse::ChallengeUpdate { challenge } => self.scene.upsert_challenge(challenge.clone()),
+            SystemResponse::HeroUpdate { hero } => match self.heroes.iter_mut().find(|existing| existing.name == hero.name) {
+                Some(existing) => *existing = hero.clone(),
+                None => self.heroes.push(hero.clone()),
+            },
+            SystemResponse::RoomSettings { settings } => self.settings = settings.clone(),
+            SystemResponse::StateRestored { settings, scene, heroes } => {
+                self.settings = settings.clone();
+                self.scene = scene.clone();
+                self.heroes = heroes.clone();
+            }
+            SystemResponse::Roll { roll_id, username, details, .. } => {
+                self.next_roll_id = roll_id + 1;
+                if details.power() > 0 {
+                    self.pending_rolls.insert(*roll_id, PendingRoll { username: username.clone(), power: details.power() });
+                }
+            }
+            SystemResponse::PowerSpent { roll_id, remaining: 0, .. } => {
+                self.pending_rolls.remove(roll_id);
+            }
+            SystemResponse::PowerSpent { roll_id, remaining, .. } => {
+                if let Some(pending) = self.pending_rolls.get_mut(roll_id) {
+                    pending.power = *remaining;
+                }
+            }
+            SystemResponse::Threat { threat_id, hero, status, tier, .. } => {
+                self.next_threat_id = threat_id + 1;
+                self.pending_threats.insert(*threat_id, PendingThreat { hero: hero.clone(), status: status.clone(), tier: *tier });
+            }
+            SystemResponse::ReactionRoll { threat_id, .. } => {
+                self.pending_threats.remove(threat_id);
+            }
+            SystemResponse::CampOpened { name } => self.camp = Some(Camp { name: name.clone(), acted: HashSet::new() }),
+            SystemResponse::CampActionTaken { hero, .. } => {
+                if let Some(camp) = self.camp.as_mut() {
+                    camp.acted.insert(hero.clone());
+                }
+            }
+            SystemResponse::CampClosed {} => self.camp = None,
+            _ => (), // announcements, with any change they make sent as updates alongside them
+        }
+    }
+
+    /// Get the latest actions taken in the room.
+    pub fn recent_history(&self) -> Vec<AuditEntry> {
+        self.history.recent()
+    }
+
+    /// Go back to the state from before the latest action.
+    fn undo(&mut self, username: &str, role: &str) -> Result<Vec<SystemResponse>, RoomError> {
+        let current = self.snapshot();
+        let (entry, before) = self.history.undo(username, role, current)?;
+        tracing::info!("{username} undid {entry}");
+        self.restore(before);
+        Ok(vec![SystemResponse::Undone { entry, by: username.to_string() }, self.restored(), SystemResponse::History { entries: self.history.recent() }])
+    }
+
+    /// Go forward to the state from after the latest undone action.
+    fn redo(&mut self, username: &str, role: &str) -> Result<Vec<SystemResponse>, RoomError> {
+        let current = self.snapshot();
+        let (entry, after) = self.history.redo(username, role, current)?;
+        tracing::info!("{username} redid {entry}");
+        self.restore(after);
+        Ok(vec![SystemResponse::Redone { entry, by: username.to_string() }, self.restored(), SystemResponse::History { entries: self.history.recent() }])
+    }
+
+    /// Copy the state of the room, without its history.
+    fn snapshot(&mut self) -> Room {
+        let history = std::mem::take(&mut self.history);
+        let snapshot = self.clone();
+        self.history = history;
+        snapshot
+    }
+
+    /// Go back or forward to a snapshot, keeping the history and seeds. Roll and threat ids keep counting up,
+    /// so a roll made after an undo can't be mistaken for the one that was undone.
+    fn restore(&mut self, snapshot: Room) {
+        let history = std::mem::take(&mut self.history);
+        let seeds = std::mem::take(&mut self.seeds);
+        *self = Room { history, seeds, next_roll_id: self.next_roll_id, next_threat_id: self.next_threat_id, ..snapshot };
+    }
+
+    /// Get the response that tells clients the room has been put back to an earlier or later state.
+    fn restored(&self) -> SystemResponse {
+        SystemResponse::StateRestored { settings: self.settings.clone(), scene: self.scene.clone(), heroes: self.heroes.clone() }
+    }
+
+    /// Apply a request to the room, returning the responses to broadcast.
+    fn apply(&mut self, request: SystemRequest) -> Result<Vec<SystemResponse>, RoomError> {
+        match request {
+            SystemRequest::Chat { username, role, content } => {
+                Ok(vec![SystemResponse::Chat { username, role, content }])
+            }
+            SystemRequest::Emote { username, content } => Ok(vec![SystemResponse::Emote { username, content }]),
+            SystemRequest::Whisper { username, to, content } => Ok(vec![SystemResponse::Whisper { from: username, to, content }]),
+            SystemRequest::Typing { username, is_typing } => Ok(vec![SystemResponse::Typing { username, is_typing }]),
+            SystemRequest::Roll { username, modifiers, client_seed } => {
+                let modifiers = self.resolve_modifiers(&modifiers)?;
+                let (used, burned) = (modifiers.tag_refs(), modifiers.burned());
+                let mut responses = self.roll(username, modifiers, client_seed)?;
+                responses.extend(self.scratch_burned(&burned));
+                responses.extend(self.expire_tags(ExpiryReason::Used, &used));
+                Ok(responses)
+            }
+            SystemRequest::SecretRoll { username, role, modifiers, client_seed } => {
+                require_narrator(&role)?;
+                let modifiers = self.resolve_modifiers(&modifiers)?;
+                let (used, burned) = (modifiers.tag_refs(), modifiers.burned());
+                let (dice_values, proof) = self.roll_dice(&username, client_seed, 0)?;
+                let (total, details) = self.score_roll(dice_values, &modifiers);
+                let commitment = proof.is_some().then(|| self.commit(&username));
+                let mut responses = vec![SystemResponse::SecretRoll { dice_values, username, modifiers, total, details, proof }];
+                responses.extend(commitment);
+                responses.extend(self.scratch_burned(&burned));
+                responses.extend(self.expire_tags(ExpiryReason::Used, &used));
+                Ok(responses)
+            }
+            SystemRequest::Threat { role, hero, status, tier, description } => {
+                require_narrator(&role)?;
+                self.threaten(hero, status, tier, description)
+            }
+            SystemRequest::ReactionRoll { username, threat_id, modifiers, client_seed } => self.react(username, threat_id, modifiers, client_seed),
+            SystemRequest::SpendPower { username, roll_id, effects } => self.spend_power(username, roll_id, effects),
+            SystemRequest::RoomSettings { role, settings } => {
+                require_narrator(&role)?;
+                self.settings = settings;
+                Ok(vec![SystemResponse::RoomSettings { settings: self.settings.clone() }])
+            }
+            SystemRequest::OpenCamp { role, name } => {
+                require_narrator(&role)?;
+                self.camp = Some(Camp { name: name.clone(), acted: HashSet::new() });
+                Ok(vec![SystemResponse::CampOpened { name }])
+            }
+            SystemRequest::CampAction { username, hero, action } => self.take_camp_action(username, hero, action),
+            SystemRequest::CloseCamp { role } => {
+                require_narrator(&role)?;
+                let camp = self.camp.take().ok_or(RoomError::NoCamp)?;
+                tracing::info!("Closed camp {}", camp.name);
+                Ok(vec![SystemResponse::CampClosed {}])
+            }
+            SystemRequest::SceneUpdate { role, scene } => {
+                require_narrator(&role)?;
+                scene.challenges.iter().try_for_each(validate_limits)?;
+                self.scene = scene;
+                let names: Vec<String> = self.scene.challenges.iter().map(|challenge| challenge.name.clone()).collect();
+                let announcements: Vec<SystemResponse> = names.iter().flat_map(|name| self.update_limits(name).0).collect();
+                let mut responses = vec![SystemResponse::SceneUpdate { scene: self.scene.clone() }];
+                responses.extend(announcements);
+                Ok(responses)
+            }
+            SystemRequest::EndScene { role } => {
+                require_narrator(&role)?;
+                Ok(self.expire_tags(ExpiryReason::SceneEnded, &[]))
+            }
+            SystemRequest::EndSession { role } => {
+                require_narrator(&role)?;
+                Ok(self.expire_tags(ExpiryReason::SessionEnded, &[]))
+            }
+            SystemRequest::HeroUpdate { username, role, mut hero } => {
+                let is_narrator = role == "narrator";
+                match self.heroes.iter_mut().find(|existing| existing.name == hero.name) {
+                    Some(existing) => {
+                        if !is_narrator {
+                            // players can't hand their hero to someone else, and only the narrator changes statuses
+                            if existing.player != username {
+                                return Err(RoomError::NotYourHero);
+                            }
+                            hero.player = existing.player.clone();
+                            hero.statuses = existing.statuses.clone();
+                        }
+                        *existing = hero.clone();
+                    }
+                    None => {
+                        if !is_narrator {
+                            if hero.player != username {
+                                return Err(RoomError::NotYourHero);
+                            }
+                            hero.statuses.clear();
+                        }
+                        self.heroes.push(hero.clone());
+                    }
+                }
+                Ok(vec![SystemResponse::HeroUpdate { hero }])
+            }
+            SystemRequest::HeroStatus { role, hero, action } => {
+                require_narrator(&role)?;
+                self.apply_hero_status(&hero, action)
+            }
+            SystemRequest::ChallengeUpdate { role, challenge } => {
+                require_narrator(&role)?;
+                validate_limits(&challenge)?;
+                let name = challenge.name.clone();
+                self.scene.upsert_challenge(challenge);
+                Ok(self.challenge_changed(&name))
+            }
+            SystemRequest::Backpack { username, hero, action } => self.use_backpack(username, hero, action),
+            SystemRequest::Reveal { role, secret, visibility } => {
+                require_narrator(&role)?;
+                self.reveal(secret, visibility)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Get the total and outcome of dice with the given modifiers.
+    fn score_roll(&self, dice_values: (i8, i8), modifiers: &ModifierMap) -> (i8, OutcomeDetails) {
+        let total = apply_mods_to_roll(dice_values.0 + dice_values.1, modifiers);
+        (total, outcome_details(self.settings.outcome_mode, total, modifiers))
+    }
+
+    /// Roll two dice for a user. When the table uses fair rolls, the dice come from the seed the user was
+    /// committed to and the client's seed, and the seed is revealed in the proof. A user who hasn't been
+    /// committed to a seed can't make a fair roll, since a seed picked now could be picked to suit the roll.
+    fn roll_dice(&mut self, username: &str, client_seed: String, nonce: u64) -> Result<((i8, i8), Option<RollProof>), RoomError> {
+        if !self.settings.fair_rolls {
+            return Ok((roll_dice(), None));
+        }
+        let server_seed = self.seeds.remove(username).ok_or(RoomError::NoCommitment)?;
+        let dice_values = fairness::fair_dice(&server_seed, &client_seed, nonce);
+        Ok((dice_values, Some(RollProof { commitment: fairness::commitment(&server_seed), server_seed, client_seed, nonce })))
+    }
+
+    /// Make a roll. After a fair roll, the user is committed to a new seed for their next one.
+    fn roll(&mut self, username: String, modifiers: ModifierMap, client_seed: String) -> Result<Vec<SystemResponse>, RoomError> {
+        let roll_id = self.next_roll_id;
+        let (dice_values, proof) = self.roll_dice(&username, client_seed, roll_id)?;
+        self.next_roll_id += 1;
+        let (total, details) = self.score_roll(dice_values, &modifiers);
+        if details.power() > 0 {
+            self.pending_rolls.insert(roll_id, PendingRoll { username: username.clone(), power: details.power() });
+        }
+        let commitment = proof.is_some().then(|| self.commit(&username));
+        let mut responses = vec![SystemResponse::Roll { roll_id, dice_values, username, modifiers, total, details, proof }];
+        responses.extend(commitment);
+        Ok(responses)
+    }
+
+    /// Pick a new seed for a user's next fair roll, returning the commitment to it to publish.
+    pub fn commit(&mut self, username: &str) -> SystemResponse {
+        let seed = new_seed();
+        let commitment = fairness::commitment(&seed);
+        self.seeds.insert(username.to_string(), seed);
+        SystemResponse::RollCommitment { username: username.to_string(), commitment }
+    }
+
+    /// Threaten a hero with a status, prompting their player to react.
+    fn threaten(&mut self, hero: String, status: String, tier: u8, description: String) -> Result<Vec<SystemResponse>, RoomError> {
+        Status::validate_tier(tier)?;
+        let player = self.hero(&hero)?.player.clone();
+        let threat_id = self.next_threat_id;
+        self.next_threat_id += 1;
+        self.pending_threats.insert(threat_id, PendingThreat { hero: hero.clone(), status: status.clone(), tier });
+        Ok(vec![SystemResponse::Threat { threat_id, hero, player, status, tier, description }])
+    }
+
+    /// Roll a hero's reaction to a threat, applying whatever is left of the status afterwards.
+    fn react(&mut self, username: String, threat_id: u64, modifiers: ModifierMap, client_seed: String) -> Result<Vec<SystemResponse>, RoomError> {
+        let threat = self.pending_threats.get(&threat_id).ok_or(RoomError::UnknownThreat(threat_id))?;
+        if self.hero(&threat.hero)?.player != username {
+            return Err(RoomError::NotYourHero);
+        }
+        let modifiers = self.resolve_modifiers(&modifiers)?;
+        let (dice_values, proof) = self.roll_dice(&username, client_seed, threat_id)?;
+        let commitment = proof.is_some().then(|| self.commit(&username));
+        let threat = self.pending_threats.remove(&threat_id).expect("threat was found");
+        let (used, burned) = (modifiers.tag_refs(), modifiers.burned());
+
+        let total = apply_mods_to_roll(dice_values.0 + dice_values.1, &modifiers);
+        let reduced_by = reaction_reduction(total, &modifiers).min(threat.tier);
+        let applied_tier = threat.tier - reduced_by;
+        let mut responses = vec![SystemResponse::ReactionRoll {
+            threat_id,
+            dice_values,
+            username,
+            modifiers,
+            total,
+            outcome: RollOutcome::from_total(total),
+            hero: threat.hero.clone(),
+            status: threat.status.clone(),
+            reduced_by,
+            applied_tier,
+            proof,
+        }];
+        responses.extend(commitment);
+        if applied_tier > 0 {
+            let action = StatusAction::Inflict { name: threat.status, tier: applied_tier };
+            responses.extend(self.apply_hero_status(&threat.hero, action)?);
+        }
+        responses.extend(self.scratch_burned(&burned));
+        responses.extend(self.expire_tags(ExpiryReason::Used, &used));
+        Ok(responses)
+    }
+
+    /// Swap the modifiers a client sent for the room's own copies of them, so a roll counts the tags as they
+    /// really are, and check that every burned modifier can be.
+    fn resolve_modifiers(&self, modifiers: &ModifierMap) -> Result<ModifierMap, RoomError> {
+        let resolved = modifiers.resolve(|tag_ref| self.modifier(tag_ref)).map_err(|tag_ref| RoomError::UnknownTag(tag_ref.scope))?;
+        match resolved
+            .get_modifiers()
+            .into_iter()
+            .find(|(modifier, polarity, is_burned)| *is_burned && !(matches!(polarity, Polarity::Positive) && modifier.can_be_burned()))
+        {
+            Some((modifier, ..)) => Err(RoomError::CantBurn(modifier.name().to_string())),
+            None => Ok(resolved),
+        }
+    }
+
+    /// Find the tag or theme a reference points to.
+    fn modifier(&self, tag_ref: &TagRef) -> Option<Modifier> {
+        if let TagScope::Hero(name) = &tag_ref.scope
+            && let Some(theme) = self.hero(name).ok()?.themes.iter().find(|theme| theme.id == tag_ref.id)
+        {
+            return Some(Modifier::Theme(theme.clone()));
+        }
+        let tag = match &tag_ref.scope {
+            TagScope::Hero(name) => {
+                let hero = self.hero(name).ok()?;
+                let tags = hero.themes.iter().flat_map(|theme| theme.power_tags.iter().chain(&theme.weakness_tags));
+                tags.chain(&hero.backpack).chain(&hero.statuses).find(|tag| tag.id() == tag_ref.id)
+            }
+            TagScope::Scene => self.scene.story_tags.iter().find(|tag| tag.id() == tag_ref.id),
+            TagScope::Challenge(name) => {
+                let challenge = self.scene.challenge(name)?;
+                challenge.tags.iter().chain(&challenge.statuses).find(|tag| tag.id() == tag_ref.id)
+            }
+            TagScope::Fellowship => None,
+        };
+        tag.cloned().map(Modifier::Tag)
+    }
+
+    /// Scratch the power and story tags burned in a roll, announcing the heroes and scene they changed.
+    /// Burned themes have nothing to scratch.
+    fn scratch_burned(&mut self, burned: &[TagRef]) -> Vec<SystemResponse> {
+        let mut responses = vec![];
+        let mut scene_changed = false;
+        for hero in &mut self.heroes {
+            let scope = TagScope::Hero(hero.name.clone());
+            let tags = hero.themes.iter_mut().flat_map(|theme| theme.power_tags.iter_mut()).chain(&mut hero.backpack);
+            if scratch_tags(&scope, tags, burned) {
+                responses.push(SystemResponse::HeroUpdate { hero: hero.clone() });
+            }
+        }
+        scene_changed |= scratch_tags(&TagScope::Scene, &mut self.scene.story_tags, burned);
+        for challenge in &mut self.scene.challenges {
+            scene_changed |= scratch_tags(&TagScope::Challenge(challenge.name.clone()), &mut challenge.tags, burned);
+        }
+        if scene_changed {
+            responses.push(SystemResponse::SceneUpdate { scene: self.scene.clone() });
+        }
+        responses
+    }
+
+    /// Apply a hero's action for the current camp, once per hero per camp.
+    fn take_camp_action(&mut self, username: String, name: String, action: CampAction) -> Result<Vec<SystemResponse>, RoomError> {
+        let camp = self.camp.as_mut().ok_or(RoomError::NoCamp)?;
+        if camp.acted.contains(&name) {
+            return Err(RoomError::AlreadyActed(name));
+        }
+        let hero = self
+            .heroes
+            .iter_mut()
+            .find(|hero| hero.name == name)
+            .ok_or_else(|| RoomError::UnknownHero(name.clone()))?;
+        if hero.player != username {
+            return Err(RoomError::NotYourHero);
+        }
+        let mut updated = hero.clone();
+        if !action.apply(&mut updated) {
+            return Err(RoomError::InvalidCampAction);
+        }
+        *hero = updated;
+        camp.acted.insert(name.clone());
+        let description = action.describe(hero);
+        tracing::info!("At {}, {} {}", camp.name, name, description);
+        Ok(vec![
+            SystemResponse::CampActionTaken { hero: name, action, description },
+            SystemResponse::HeroUpdate { hero: hero.clone() },
+        ])
+    }
+
+    /// Remove every tag that expires for the given reason, announcing what expired and what it changed.
+    /// Single use tags only expire if they are among the tags that were used. Challenges that lose a status
+    /// have their limits brought up to date.
+    fn expire_tags(&mut self, reason: ExpiryReason, used: &[TagRef]) -> Vec<SystemResponse> {
+        let mut expired = Vec::new();
+        let mut updates = Vec::new();
+        let mut take_expired = |scope: TagScope, tags: &mut Vec<Tag>| {
+            let removed: Vec<Tag> = tags
+                .extract_if(.., |tag| {
+                    reason.expires(tag.duration())
+                        && (reason != ExpiryReason::Used || used.contains(&TagRef::new(scope.clone(), tag.id())))
+                })
+                .collect();
+            let changed = !removed.is_empty();
+            expired.extend(removed.into_iter().map(|tag| (scope.clone(), tag)));
+            changed
+        };
+
+        for hero in &mut self.heroes {
+            let scope = TagScope::Hero(hero.name.clone());
+            let backpack_changed = take_expired(scope.clone(), &mut hero.backpack);
+            if take_expired(scope, &mut hero.statuses) || backpack_changed {
+                updates.push(SystemResponse::HeroUpdate { hero: hero.clone() });
+            }
+        }
+        let mut scene_changed = take_expired(TagScope::Scene, &mut self.scene.story_tags);
+        let mut lost_statuses = vec![];
+        for challenge in &mut self.scene.challenges {
+            let scope = TagScope::Challenge(challenge.name.clone());
+            scene_changed |= take_expired(scope.clone(), &mut challenge.tags);
+            if take_expired(scope, &mut challenge.statuses) {
+                lost_statuses.push(challenge.name.clone());
+                scene_changed = true;
+            }
+        }
+        let mut announcements = vec![];
+        for name in &lost_statuses {
+            announcements.extend(self.update_limits(name).0);
+        }
+        if scene_changed {
+            updates.push(SystemResponse::SceneUpdate { scene: self.scene.clone() });
+        }
+
+        if expired.is_empty() {
+            return vec![];
+        }
+        let tag_visibility = expired
+            .iter()
+            .map(|(scope, tag)| (tag.id(), self.scene.tag_visibility(scope, tag)))
+            .filter(|(_, visibility)| *visibility != Visibility::Revealed)
+            .collect();
+        let mut responses = vec![SystemResponse::TagsExpired { reason, tags: expired, tag_visibility }];
+        responses.extend(updates);
+        responses.extend(announcements);
+        responses
+    }
+
+    /// Announce a changed challenge, bringing its limits up to date.
+    fn challenge_changed(&mut self, name: &str) -> Vec<SystemResponse> {
+        let (announcements, dropped_loot) = self.update_limits(name);
+        let Some(challenge) = self.scene.challenge(name) else {
+            return vec![];
+        };
+        let mut responses = vec![SystemResponse::ChallengeUpdate { challenge: challenge.clone() }];
+        responses.extend(announcements);
+        if dropped_loot {
+            responses.push(SystemResponse::SceneUpdate { scene: self.scene.clone() });
+        }
+        responses
+    }
+
+    /// Bring a challenge's limits up to date, announcing any it reached. A challenge that is defeated
+    /// drops its loot into the scene. Returns the announcements and whether any loot was dropped.
+    fn update_limits(&mut self, name: &str) -> (Vec<SystemResponse>, bool) {
+        let Some(challenge) = self.scene.challenge_mut(name) else {
+            return (vec![], false);
+        };
+        let reached = challenge.update_limits();
+        let mut responses = vec![];
+        let mut defeated = false;
+        for limit in reached {
+            tracing::info!("{} reached its {} limit", challenge.name, limit.name);
+            defeated |= limit.outcome == LimitOutcome::Defeated;
+            responses.push(SystemResponse::LimitReached {
+                challenge: challenge.name.clone(),
+                limit,
+                challenge_visibility: challenge.visibility,
+            });
+        }
+        if !defeated {
+            return (responses, false);
+        }
+        let tags = generate_loot(challenge);
+        if tags.is_empty() {
+            return (responses, false);
+        }
+        // loot from a challenge players can't fully see is no more visible than the challenge was
+        let challenge_visibility = challenge.visibility;
+        let loot: Vec<Tag> = tags.iter().map(|tag| Tag::new_story(tag)).collect();
+        if challenge_visibility != Visibility::Revealed {
+            self.scene.tag_visibility.extend(loot.iter().map(|tag| (tag.id(), challenge_visibility)));
+        }
+        self.scene.story_tags.extend(loot);
+        responses.push(SystemResponse::LootDropped { challenge: name.to_string(), tags, challenge_visibility });
+        (responses, true)
+    }
+
+    /// Apply a backpack action to one of the player's heroes.
+    fn use_backpack(&mut self, username: String, name: String, action: BackpackAction) -> Result<Vec<SystemResponse>, RoomError> {
+        if self.hero(&name)?.player != username {
+            return Err(RoomError::NotYourHero);
+        }
+        let mut responses = vec![];
+        let tag_name = match &action {
+            BackpackAction::Add { name: tag, duration } => {
+                self.hero_mut(&name)?.backpack.push(Tag::new_story(tag).with_duration(*duration));
+                tag.clone()
+            }
+            BackpackAction::Remove { tag } => self.take_from_backpack(&name, *tag)?.name().to_string(),
+            BackpackAction::Scratch { tag } => {
+                let item = self
+                    .hero_mut(&name)?
+                    .backpack
+                    .iter_mut()
+                    .find(|item| item.id() == *tag)
+                    .ok_or(RoomError::NotInBackpack)?;
+                item.scratch();
+                item.name().to_string()
+            }
+            BackpackAction::Transfer { tag, to } => {
+                self.hero(to)?;
+                let item = self.take_from_backpack(&name, *tag)?;
+                let tag_name = item.name().to_string();
+                let recipient = self.hero_mut(to)?;
+                recipient.backpack.push(item);
+                responses.push(SystemResponse::HeroUpdate { hero: recipient.clone() });
+                tag_name
+            }
+            BackpackAction::Drop { tag } => {
+                let item = self.take_from_backpack(&name, *tag)?;
+                let tag_name = item.name().to_string();
+                self.scene.story_tags.push(item);
+                responses.push(SystemResponse::SceneUpdate { scene: self.scene.clone() });
+                tag_name
+            }
+            BackpackAction::PickUp { tag } => {
+                let index = self
+                    .scene
+                    .story_tags
+                    .iter()
+                    .position(|item| item.id() == *tag)
+                    .ok_or(RoomError::NotInScene)?;
+                let item = self.scene.story_tags.remove(index);
+                let tag_name = item.name().to_string();
+                self.hero_mut(&name)?.backpack.push(item);
+                responses.push(SystemResponse::SceneUpdate { scene: self.scene.clone() });
+                tag_name
+            }
+        };
+        responses.insert(0, SystemResponse::HeroUpdate { hero: self.hero(&name)?.clone() });
+        let description = action.describe(&tag_name);
+        responses.insert(0, SystemResponse::BackpackChanged { hero: name, action, description });
+        Ok(responses)
+    }
+
+    fn take_from_backpack(&mut self, hero: &str, tag: Uuid) -> Result<Tag, RoomError> {
+        let backpack = &mut self.hero_mut(hero)?.backpack;
+        let index = backpack.iter().position(|item| item.id() == tag).ok_or(RoomError::NotInBackpack)?;
+        Ok(backpack.remove(index))
+    }
+
+    fn hero_mut(&mut self, name: &str) -> Result<&mut Hero, RoomError> {
+        self.heroes
+            .iter_mut()
+            .find(|hero| hero.name == name)
+            .ok_or_else(|| RoomError::UnknownHero(name.to_string()))
+    }
+
+    fn hero(&self, name: &str) -> Result<&Hero, RoomError> {
+        self.heroes
+            .iter()
+            .find(|hero| hero.name == name)
+            .ok_or_else(|| RoomError::UnknownHero(name.to_string()))
+    }
+
+    /// Spend power from a roll on effects, applying them to the scene.
+    fn spend_power(&mut self, username: String, roll_id: u64, effects: Vec<Effect>) -> Result<Vec<SystemResponse>, RoomError> {
+        if self.settings.outcome_mode == OutcomeMode::Quick {
+            return Err(RoomError::QuickOutcomes);
+        }
+        let pending = self.pending_rolls.get(&roll_id).ok_or(RoomError::UnknownRoll(roll_id))?;
+        if pending.username != username {
+            return Err(RoomError::NotYourRoll);
+        }
+        if effects.len() > MAX_EFFECTS {
+            return Err(RoomError::TooManyEffects(effects.len()));
+        }
+        let cost = effects
+            .iter()
+            .try_fold(0u16, |cost, effect| cost.checked_add(u16::from(effect.cost())))
+            .ok_or(RoomError::TooManyEffects(effects.len()))?;
+        let remaining = u8::try_from(cost)
+            .ok()
+            .and_then(|cost| pending.power.checked_sub(cost))
+            .ok_or(RoomError::NotEnoughPower { cost, remaining: pending.power })?;
+        self.validate_effects(&effects)?;
+
+        let mut responses = vec![];
+        let mut scene_changed = false;
+        for effect in &effects {
+            match effect {
+                Effect::CreateStoryTag { name, duration } => {
+                    self.scene.story_tags.push(Tag::new_story(name).with_duration(*duration));
+                    scene_changed = true;
+                }
+                Effect::InflictStatus { target: TagScope::Challenge(challenge), name, tier } => {
+                    self.scene.challenge_mut(challenge).expect("challenge was validated").inflict_status(name, *tier)?;
+                    responses.extend(self.challenge_changed(challenge));
+                }
+                Effect::InflictStatus { target: TagScope::Hero(hero), name, tier } => {
+                    let action = StatusAction::Inflict { name: name.clone(), tier: *tier };
+                    responses.extend(self.apply_hero_status(hero, action)?);
+                }
+                Effect::ReduceStatus { status, amount } => match &status.scope {
+                    TagScope::Challenge(challenge) => {
+                        self.scene.challenge_mut(challenge).expect("challenge was validated").reduce_status(status.id, *amount);
+                        responses.extend(self.challenge_changed(challenge));
+                    }
+                    TagScope::Hero(hero) => {
+                        let action = StatusAction::Reduce { status: status.id, amount: *amount };
+                        responses.extend(self.apply_hero_status(hero, action)?);
+                    }
+                    _ => unreachable!("scope was validated"),
+                },
+                Effect::InflictStatus { .. } => unreachable!("scope was validated"),
+                Effect::Discover { .. } | Effect::ExtraFeat { .. } => (), // answered by the narrator
+            }
+        }
+        if scene_changed {
+            responses.push(SystemResponse::SceneUpdate { scene: self.scene.clone() });
+        }
+
+        if remaining == 0 {
+            self.pending_rolls.remove(&roll_id);
+        } else if let Some(pending) = self.pending_rolls.get_mut(&roll_id) {
+            pending.power = remaining;
+        }
+        let challenge_visibility = effects
+            .iter()
+            .filter_map(Effect::challenge)
+            .map(|name| (name.to_string(), self.scene.challenge_visibility(name)))
+            .filter(|(_, visibility)| *visibility != Visibility::Revealed)
+            .collect();
+        responses.insert(0, SystemResponse::PowerSpent { username, roll_id, effects, remaining, challenge_visibility });
+        Ok(responses)
+    }
+
+    /// Apply a narrator's status action to a hero, announcing if it takes them out.
+    fn apply_hero_status(&mut self, name: &str, action: StatusAction) -> Result<Vec<SystemResponse>, RoomError> {
+        if let StatusAction::Inflict { tier, .. } = &action {
+            Status::validate_tier(*tier)?;
+        }
+        let hero = self.hero_mut(name)?;
+        let was_taken_out = hero.taken_out_by().is_some();
+        let target = match &action {
+            StatusAction::Inflict { name, .. } => name.clone(),
+            StatusAction::Reduce { status, .. } | StatusAction::Clear { status } => {
+                hero.statuses.iter().find(|existing| existing.id() == *status).map(|existing| existing.name().to_string()).unwrap_or_default()
+            }
+        };
+        if !hero.apply_status_action(&action)? {
+            return Err(RoomError::UnknownStatus(name.to_string()));
+        }
+        let description = match &action {
+            StatusAction::Inflict { .. } => {
+                let label = hero.statuses.iter().filter_map(Tag::status).find(|status| status.name.eq_ignore_ascii_case(&target)).map(Status::label);
+                format!("is now {}", label.unwrap_or(target))
+            }
+            StatusAction::Reduce { amount, .. } => format!("has {{{target}}} reduced by {amount}"),
+            StatusAction::Clear { .. } => format!("is no longer {{{target}}}"),
+        };
+        let mut responses = vec![
+            SystemResponse::HeroUpdate { hero: hero.clone() },
+            SystemResponse::StatusChanged { hero: hero.name.clone(), description },
+        ];
+        if let (Some(status), false) = (hero.taken_out_by(), was_taken_out) {
+            tracing::info!("{} was taken out by {}", hero.name, status.name());
+            responses.push(SystemResponse::HeroTakenOut { hero: hero.name.clone(), status: status.clone() });
+        }
+        Ok(responses)
+    }
+
+    /// Change how much players can see of a challenge, tag or limit, announcing it unless it is being hidden.
+    fn reveal(&mut self, secret: Secret, visibility: Visibility) -> Result<Vec<SystemResponse>, RoomError> {
+        let description = match &secret {
+            Secret::Challenge(name) => {
+                let challenge = self.scene.challenge_mut(name).ok_or_else(|| RoomError::UnknownChallenge(name.clone()))?;
+                challenge.visibility = visibility;
+                format!("{name} is {visibility}")
+            }
+            Secret::Tag(TagRef { scope, id }) => {
+                let (tags, tag_visibility) = match scope {
+                    TagScope::Scene => (self.scene.story_tags.iter().collect::<Vec<_>>(), &mut self.scene.tag_visibility),
+                    TagScope::Challenge(name) => {
+                        let challenge = self.scene.challenge_mut(name).ok_or_else(|| RoomError::UnknownChallenge(name.clone()))?;
+                        (challenge.tags.iter().chain(&challenge.statuses).collect(), &mut challenge.tag_visibility)
+                    }
+                    _ => return Err(RoomError::UnknownSecret),
+                };
+                let tag = tags.into_iter().find(|tag| tag.id() == *id).ok_or(RoomError::UnknownSecret)?;
+                let description = match visibility {
+                    Visibility::Revealed => format!("[{}] on {scope} is revealed", tag.name()),
+                    _ => format!("something on {scope} is {visibility}"),
+                };
+                match visibility {
+                    Visibility::Revealed => tag_visibility.remove(id),
+                    _ => tag_visibility.insert(*id, visibility),
+                };
+                description
+            }
+            Secret::Limit { challenge, limit } => {
+                let found = self
+                    .scene
+                    .challenge_mut(challenge)
+                    .ok_or_else(|| RoomError::UnknownChallenge(challenge.clone()))?
+                    .limits
+                    .iter_mut()
+                    .find(|existing| existing.name == *limit)
+                    .ok_or(RoomError::UnknownSecret)?;
+                found.visibility = visibility;
+                format!("{challenge}'s {limit} limit is {visibility}")
+            }
+        };
+        tracing::info!("{description}");
+        let challenge_visibility = match &secret {
+            Secret::Challenge(name) | Secret::Limit { challenge: name, .. } | Secret::Tag(TagRef { scope: TagScope::Challenge(name), .. }) => {
+                self.scene.challenge_visibility(name)
+            }
+            Secret::Tag(_) => Visibility::Revealed,
+        };
+        Ok(vec![
+            SystemResponse::Revealed { secret, visibility, description, challenge_visibility },
+            SystemResponse::SceneUpdate { scene: self.scene.clone() },
+        ])
+    }
+
+    /// Check every effect can be applied before any of them are, so a bad effect doesn't leave the spend half done.
+    fn validate_effects(&self, effects: &[Effect]) -> Result<(), RoomError> {
+        for effect in effects {
+            match effect {
+                Effect::InflictStatus { target, tier, .. } => {
+                    Status::validate_tier(*tier)?;
+                    self.statuses(target)?;
+                }
+                Effect::ReduceStatus { status, .. }
+                    if !self.statuses(&status.scope)?.iter().any(|existing| existing.id() == status.id) =>
+                {
+                    return Err(RoomError::UnknownStatus(status.scope.to_string()));
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the statuses of the challenge or hero in the given scope.
+    fn statuses(&self, scope: &TagScope) -> Result<&[Tag], RoomError> {
+        match scope {
+            TagScope::Challenge(name) => self
+                .scene
+                .challenge(name)
+                .map(|challenge| challenge.statuses.as_slice())
+                .ok_or_else(|| RoomError::UnknownChallenge(name.clone())),
+            TagScope::Hero(name) => Ok(&self.hero(name)?.statuses),
+            _ => Err(RoomError::NoStatuses(scope.clone())),
+        }
+    }
+}
+
+/// Pick story tags from a defeated challenge's loot table, one per point of rating.
+fn generate_loot(challenge: &Challenge) -> Vec<String> {
+    let count = challenge.rating.max(1) as usize;
+    challenge.loot.choose_multiple(&mut rand::rng(), count).cloned().collect()
+}
+
+/// Scratch the tags in a scope that were burned, returning whether any were.
+fn scratch_tags<'a>(scope: &TagScope, tags: impl IntoIterator<Item = &'a mut Tag>, burned: &[TagRef]) -> bool {
+    let mut scratched = false;
+    for tag in tags.into_iter().filter(|tag| burned.contains(&TagRef::new(scope.clone(), tag.id()))) {
+        tag.scratch();
+        scratched = true;
+    }
+    scratched
+}
+
+fn require_narrator(role: &str) -> Result<(), RoomError> {
+    if role == "narrator" { Ok(()) } else { Err(RoomError::NotNarrator) }
+}
+
+/// Make a random seed for fair rolls, as hex.
+fn new_seed() -> String {
+    rand::random::<[u8; 32]>().iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Check every limit on a challenge has a maximum, since one without would count as reached from the start.
+fn validate_limits(challenge: &Challenge) -> Result<(), RoomError> {
+    match challenge.limits.iter().find(|limit| limit.max == 0) {
+        Some(limit) => Err(RoomError::NoLimitMax { challenge: challenge.name.clone(), limit: limit.name.clone() }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use shared::{messaging::SystemResponse, modifier::ModifierMap, roll::RollOutcome};
+
+/// How many of the most used tags are listed in a summary.
+const MOST_USED_TAGS: usize = 5;
+
+/// Running totals for a set of rolls.
+#[derive(Default)]
+struct Tally {
+    rolls: u32,
+    sum: i64,
+    successes: u32,
+    mixed: u32,
+    misses: u32,
+    tags: HashMap<String, u32>,
+}
+
+impl Tally {
+    fn add(&mut self, total: i8, modifiers: &ModifierMap) {
+        self.rolls += 1;
+        self.sum += i64::from(total);
+        match RollOutcome::from_total(total) {
+            RollOutcome::Success => self.successes += 1,
+            RollOutcome::Mixed => self.mixed += 1,
+            RollOutcome::Miss => self.misses += 1,
+        }
+        for (modifier, ..) in modifiers.get_modifiers() {
+            *self.tags.entry(modifier.name().to_string()).or_default() += 1;
+        }
+    }
+
+    fn summary(&self) -> Summary {
+        let mut most_used_tags: Vec<(String, u32)> = self.tags.iter().map(|(name, uses)| (name.clone(), *uses)).collect();
+        most_used_tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        most_used_tags.truncate(MOST_USED_TAGS);
+        Summary {
+            rolls: self.rolls,
+            average: if self.rolls == 0 { 0.0 } else { self.sum as f64 / f64::from(self.rolls) },
+            successes: self.successes,
+            mixed: self.mixed,
+            misses: self.misses,
+            most_used_tags,
+        }
+    }
+}
+
+/// What a set of rolls came to: the average total, how many of each outcome, and the tags used most.
+#[derive(Serialize)]
+pub struct Summary {
+    rolls: u32,
+    average: f64,
+    successes: u32,
+    mixed: u32,
+    misses: u32,
+    most_used_tags: Vec<(String, u32)>,
+}
+
+/// Roll statistics for the table this session, and for each player since the server started.
+#[derive(Serialize)]
+pub struct Report {
+    session: Summary,
+    players: BTreeMap<String, Summary>,
+}
+
+/// Statistics on the rolls made in a campaign. Secret rolls aren't counted, since players can see the statistics.
+#[derive(Default)]
+pub struct RollStats {
+    session: Tally,
+    players: HashMap<String, Tally>,
+}
+
+impl RollStats {
+    /// Count a response if it's a roll or a reaction roll.
+    pub fn record(&mut self, response: &SystemResponse) {
+        let (username, total, modifiers) = match response {
+            SystemResponse::Roll { username, total, modifiers, .. } | SystemResponse::ReactionRoll { username, total, modifiers, .. } => {
+                (username, *total, modifiers)
+            }
+            _ => return,
+        };
+        self.session.add(total, modifiers);
+        self.players.entry(username.clone()).or_default().add(total, modifiers);
+    }
+
+    /// Start counting a new session.
+    pub fn end_session(&mut self) {
+        self.session = Tally::default();
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            session: self.session.summary(),
+            players: self.players.iter().map(|(username, tally)| (username.clone(), tally.summary())).collect(),
+        }
+    }
+}
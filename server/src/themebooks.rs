@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use shared::themebook::Themebook;
+
+/// The directory themebooks are loaded from when `THEMEBOOK_DIR` isn't set.
+pub const DEFAULT_THEMEBOOK_DIR: &str = "themebooks";
+
+/// Load every `.toml` and `.json` themebook in a directory, skipping any that can't be read or parsed.
+pub fn load_themebooks(dir: &Path) -> Vec<Themebook> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            tracing::warn!("Couldn't read themebook directory {}: {err}", dir.display());
+            return vec![];
+        }
+    };
+    let mut themebooks: Vec<Themebook> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter_map(|path| match load_themebook(&path) {
+            Ok(themebook) => themebook,
+            Err(err) => {
+                tracing::warn!("Skipping themebook {}: {err}", path.display());
+                None
+            }
+        })
+        .collect();
+    themebooks.sort_by(|a, b| a.name.cmp(&b.name));
+    tracing::info!("Loaded {} themebooks from {}", themebooks.len(), dir.display());
+    themebooks
+}
+
+/// Load a single themebook file, returning `None` for files that aren't themebooks.
+fn load_themebook(path: &Path) -> Result<Option<Themebook>, String> {
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    if !matches!(extension, Some("toml" | "json")) {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let themebook = match extension {
+        Some("toml") => toml::from_str(&content).map_err(|err| err.to_string())?,
+        _ => serde_json::from_str(&content).map_err(|err| err.to_string())?,
+    };
+    Ok(Some(themebook))
+}
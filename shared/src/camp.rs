@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{hero::Hero, tag::{Tag, TagDuration}};
+
+/// The most story tags a hero can prepare in a single camp action.
+pub const MAX_PREPARED_TAGS: usize = 2;
+
+/// What a hero does during a camp or downtime scene.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum CampAction {
+    /// Reduce every status by one tier.
+    Rest,
+    /// Recover all scratched tags.
+    Recover,
+    /// Reflect on a theme, marking improve on it.
+    Reflect { theme: Uuid },
+    /// Prepare story tags into the backpack, lasting for the given duration.
+    Prepare { tags: Vec<String>, #[serde(default)] duration: TagDuration },
+}
+
+impl CampAction {
+    /// Apply the action to a hero. Returns false if the action isn't valid for the hero.
+    pub fn apply(&self, hero: &mut Hero) -> bool {
+        match self {
+            CampAction::Rest => {
+                for status in hero.statuses.iter_mut().filter_map(Tag::status_mut) {
+                    status.reduce(1);
+                }
+                hero.statuses.retain(|status| !status.is_cleared());
+            }
+            CampAction::Recover => {
+                let tags = hero
+                    .themes
+                    .iter_mut()
+                    .flat_map(|theme| theme.power_tags.iter_mut())
+                    .chain(hero.backpack.iter_mut());
+                for tag in tags {
+                    tag.unscratch();
+                }
+            }
+            CampAction::Reflect { theme } => {
+                let Some(theme) = hero.themes.iter_mut().find(|t| t.id == *theme) else {
+                    return false;
+                };
+                theme.improve += 1;
+            }
+            CampAction::Prepare { tags, duration } => {
+                if tags.is_empty() || tags.len() > MAX_PREPARED_TAGS {
+                    return false;
+                }
+                hero.backpack.extend(tags.iter().map(|name| Tag::new_story(name).with_duration(*duration)));
+            }
+        }
+        true
+    }
+}
+
+impl CampAction {
+    /// Describe the action in a human-readable format, looking up theme names on the hero.
+    pub fn describe(&self, hero: &Hero) -> String {
+        match self {
+            CampAction::Rest => "rests".to_string(),
+            CampAction::Recover => "recovers their scratched tags".to_string(),
+            CampAction::Reflect { theme } => {
+                let theme = hero.themes.iter().find(|t| t.id == *theme).map_or("a theme", |t| &t.name);
+                format!("reflects on {theme}")
+            }
+            CampAction::Prepare { tags, .. } => {
+                let tags: Vec<String> = tags.iter().map(|tag| format!("[{tag}]")).collect();
+                format!("prepares {}", tags.join(" "))
+            }
+        }
+    }
+}
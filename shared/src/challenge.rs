@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{limit::Limit, status::StatusError, tag::{self, Tag}, visibility::Visibility};
+
+/// An obstacle, foe or situation the heroes face in a scene.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Challenge {
+    pub name: String,
+    pub rating: u8,
+    pub tags: Vec<Tag>,
+    pub statuses: Vec<Tag>,
+    pub limits: Vec<Limit>,
+    pub threats: Vec<String>,
+    /// Story tags the challenge can drop into the scene when it is defeated.
+    #[serde(default)]
+    pub loot: Vec<String>,
+    /// How much of the challenge players can see.
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// How much of each of the challenge's tags and statuses players can see, by id. Unlisted ones are revealed.
+    #[serde(default)]
+    pub tag_visibility: HashMap<Uuid, Visibility>,
+}
+
+impl Challenge {
+    /// Create a new challenge with the given name and rating.
+    pub fn new(name: &str, rating: u8) -> Self {
+        Challenge {
+            name: name.to_string(),
+            rating,
+            ..Default::default()
+        }
+    }
+
+    /// Get the status with the given id.
+    pub fn status(&self, id: Uuid) -> Option<&Tag> {
+        self.statuses.iter().find(|status| status.id() == id)
+    }
+
+    /// Give the challenge a status of the given tier, stacking onto an existing status of the same name.
+    pub fn inflict_status(&mut self, name: &str, tier: u8) -> Result<(), StatusError> {
+        tag::inflict_status(&mut self.statuses, name, tier)
+    }
+
+    /// Set each limit's progress to the highest tier among its matching statuses.
+    /// Returns the limits that were reached by this update.
+    pub fn update_limits(&mut self) -> Vec<Limit> {
+        let mut reached = vec![];
+        for limit in self.limits.iter_mut() {
+            let was_reached = limit.is_reached();
+            limit.progress = self
+                .statuses
+                .iter()
+                .filter(|status| limit.matches(status.name()))
+                .map(Tag::get_value)
+                .max()
+                .unwrap_or(0);
+            if limit.is_reached() && !was_reached {
+                reached.push(limit.clone());
+            }
+        }
+        reached
+    }
+
+    /// Reduce the status with the given id by the given number of tiers, removing it once cleared.
+    /// Returns false if the challenge has no such status.
+    pub fn reduce_status(&mut self, id: Uuid, amount: u8) -> bool {
+        tag::reduce_status(&mut self.statuses, id, amount)
+    }
+}
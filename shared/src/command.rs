@@ -0,0 +1,380 @@
+use crate::{
+    hero::Hero,
+    modifier::{Modifier, ModifierMap, Polarity},
+    scene::Scene,
+    status::{Status, StatusError},
+    tag::TagScope,
+};
+
+/// A slash command typed into the chat input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// `/roll +Strong +Agile! -Clumsy -{Poisoned:4}`
+    Roll { modifiers: String },
+    /// `/secret <modifiers>`, a roll only the narrator sees
+    SecretRoll { modifiers: String },
+    /// `/whisper <user> <message>`
+    Whisper { to: String, content: String },
+    /// `/me <action>`
+    Me { content: String },
+    /// `/status <hero> <status>-<tier>`
+    Status { hero: String, name: String, tier: u8 },
+}
+
+/// Reasons a command or roll can't be understood.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandError {
+    UnknownCommand(String),
+    Usage(&'static str),
+    MissingSign(String),
+    Unclosed(String),
+    UnknownTag(String),
+    CantBurn(String),
+    InvalidStatus(String),
+    WrongTier { name: String, tier: u8, typed: u8 },
+    Status(StatusError),
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::UnknownCommand(name) => write!(f, "there is no /{name} command"),
+            CommandError::Usage(usage) => write!(f, "usage: {usage}"),
+            CommandError::MissingSign(token) => write!(f, "{token} needs a + or - in front of it"),
+            CommandError::Unclosed(token) => write!(f, "{token} is missing its closing bracket"),
+            CommandError::UnknownTag(name) => write!(f, "there is no tag or theme called {name}"),
+            CommandError::CantBurn(name) => write!(f, "{name} can't be burned, only unscratched power and story tags and themes can"),
+            CommandError::InvalidStatus(text) => write!(f, "{text} isn't a status, write it like poisoned-3"),
+            CommandError::WrongTier { name, tier, typed } => write!(f, "{name} is at tier {tier}, not {typed}"),
+            CommandError::Status(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl From<StatusError> for CommandError {
+    fn from(error: StatusError) -> Self {
+        CommandError::Status(error)
+    }
+}
+
+impl Command {
+    /// Parse a line starting with `/` into a command.
+    pub fn parse(input: &str) -> Result<Self, CommandError> {
+        let input = input.trim().trim_start_matches('/');
+        let (name, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let args = args.trim();
+        match name.to_lowercase().as_str() {
+            "roll" | "r" => Ok(Command::Roll { modifiers: args.to_string() }),
+            "secret" => Ok(Command::SecretRoll { modifiers: args.to_string() }),
+            "whisper" | "w" => match args.split_once(char::is_whitespace) {
+                Some((to, content)) if !content.trim().is_empty() => {
+                    Ok(Command::Whisper { to: to.to_string(), content: content.trim().to_string() })
+                }
+                _ => Err(CommandError::Usage("/whisper <user> <message>")),
+            },
+            "me" if !args.is_empty() => Ok(Command::Me { content: args.to_string() }),
+            "me" => Err(CommandError::Usage("/me <action>")),
+            "status" => {
+                let Some((hero, status)) = args.split_once(char::is_whitespace) else {
+                    return Err(CommandError::Usage("/status <hero> <status>-<tier>"));
+                };
+                let status = status.trim();
+                let (name, tier) = status
+                    .rsplit_once('-')
+                    .and_then(|(name, tier)| Some((name.trim(), tier.trim().parse::<u8>().ok()?)))
+                    .filter(|(name, _)| !name.is_empty())
+                    .ok_or_else(|| CommandError::InvalidStatus(status.to_string()))?;
+                Status::validate_tier(tier)?;
+                Ok(Command::Status { hero: hero.to_string(), name: name.to_string(), tier })
+            }
+            _ => Err(CommandError::UnknownCommand(name.to_string())),
+        }
+    }
+}
+
+/// What kind of modifier a roll token names, going by its brackets.
+#[derive(Clone, Copy, PartialEq)]
+enum TokenKind {
+    Any,
+    Tag,
+    Theme,
+    Status,
+}
+
+/// A single `+Name`, `+Name!`, `-[Name]`, `+<Theme>` or `-{Status:tiers}` in a roll.
+struct RollToken {
+    polarity: Polarity,
+    is_burned: bool,
+    kind: TokenKind,
+    name: String,
+    tiers: Vec<u8>,
+}
+
+/// Split roll text into tokens. Also accepts what `ModifierMap` displays, such as `+++[Agile]+++` and `-{Poisoned:4}-`.
+fn tokenize(input: &str) -> Result<Vec<RollToken>, CommandError> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let polarity = match c {
+            '+' => Polarity::Positive,
+            '-' => Polarity::Negative,
+            _ => {
+                let word: String = chars.by_ref().take_while(|c| !c.is_whitespace()).collect();
+                return Err(CommandError::MissingSign(word));
+            }
+        };
+        let mut signs = 0;
+        while chars.next_if_eq(&c).is_some() {
+            signs += 1;
+        }
+
+        let close = match chars.peek() {
+            Some('[') => Some((']', TokenKind::Tag)),
+            Some('<') => Some(('>', TokenKind::Theme)),
+            Some('{') => Some(('}', TokenKind::Status)),
+            _ => None,
+        };
+        let (kind, mut text) = match close {
+            Some((close, kind)) => {
+                let open = chars.next().expect("bracket was peeked");
+                let mut text = String::new();
+                let mut is_closed = false;
+                for c in chars.by_ref() {
+                    if c == close {
+                        is_closed = true;
+                        break;
+                    }
+                    text.push(c);
+                }
+                if !is_closed {
+                    return Err(CommandError::Unclosed(format!("{open}{text}")));
+                }
+                (kind, text)
+            }
+            None => {
+                let mut text = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    text.push(c);
+                }
+                (TokenKind::Any, text)
+            }
+        };
+        while chars.next_if(|&next| next == c).is_some() {}
+        let mut is_burned = signs >= 3;
+        if chars.next_if_eq(&'!').is_some() || text.ends_with('!') {
+            text = text.trim_end_matches('!').to_string();
+            is_burned = true;
+        }
+
+        let (name, tiers) = match (kind, text.split_once(':')) {
+            (TokenKind::Status, Some((name, tiers))) => {
+                let tiers = tiers
+                    .split(',')
+                    .map(|tier| tier.trim().parse::<u8>().map_err(|_| CommandError::InvalidStatus(text.clone())))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (name.trim().to_string(), tiers)
+            }
+            _ => (text.trim().to_string(), vec![]),
+        };
+        tokens.push(RollToken { polarity, is_burned, kind, name, tiers });
+    }
+    Ok(tokens)
+}
+
+/// Every tag and theme that can be brought into a roll, along with where it comes from.
+fn candidates(hero: Option<&Hero>, scene: &Scene) -> Vec<(TagScope, Modifier)> {
+    let mut candidates = vec![];
+    if let Some(hero) = hero {
+        let scope = TagScope::Hero(hero.name.clone());
+        for theme in &hero.themes {
+            candidates.push((scope.clone(), Modifier::Theme(theme.clone())));
+            for tag in theme.power_tags.iter().chain(&theme.weakness_tags) {
+                candidates.push((scope.clone(), Modifier::Tag(tag.clone())));
+            }
+        }
+        for tag in hero.backpack.iter().chain(&hero.statuses) {
+            candidates.push((scope.clone(), Modifier::Tag(tag.clone())));
+        }
+    }
+    for tag in &scene.story_tags {
+        candidates.push((TagScope::Scene, Modifier::Tag(tag.clone())));
+    }
+    for challenge in &scene.challenges {
+        let scope = TagScope::Challenge(challenge.name.clone());
+        for tag in challenge.tags.iter().chain(&challenge.statuses) {
+            candidates.push((scope.clone(), Modifier::Tag(tag.clone())));
+        }
+    }
+    candidates
+}
+
+/// Whether a modifier is the kind a token's brackets ask for.
+fn is_kind(modifier: &Modifier, kind: TokenKind) -> bool {
+    match kind {
+        TokenKind::Any => true,
+        TokenKind::Tag => matches!(modifier, Modifier::Tag(tag) if tag.status().is_none()),
+        TokenKind::Theme => matches!(modifier, Modifier::Theme(_)),
+        TokenKind::Status => matches!(modifier, Modifier::Tag(tag) if tag.status().is_some()),
+    }
+}
+
+/// Build the status a token writes out, such as `{Poisoned:2,4}`, if it has tiers.
+fn typed_status(token: &RollToken) -> Result<Option<Status>, CommandError> {
+    let [first, rest @ ..] = token.tiers.as_slice() else {
+        return Ok(None);
+    };
+    let mut status = Status::new(&token.name, *first)?;
+    for tier in rest {
+        status.add_tier(*tier)?;
+    }
+    Ok(Some(status))
+}
+
+/// Parse roll text such as `+Strong +Agile! -Clumsy -{Poisoned:4}` into modifiers, resolving each name
+/// against the hero's themes, tags and statuses and the scene's story tags and challenges, ignoring case.
+/// A `!` burns a tag. A status written with tiers must be at the tier written.
+pub fn parse_roll(input: &str, hero: Option<&Hero>, scene: &Scene) -> Result<ModifierMap, CommandError> {
+    let candidates = candidates(hero, scene);
+    let mut modifiers = ModifierMap::default();
+    for token in tokenize(input)? {
+        let found = candidates
+            .iter()
+            .find(|(_, modifier)| is_kind(modifier, token.kind) && modifier.name().eq_ignore_ascii_case(&token.name));
+        let Some((scope, modifier)) = found.cloned() else {
+            return Err(CommandError::UnknownTag(token.name));
+        };
+        if let Modifier::Tag(tag) = &modifier
+            && let (Some(status), Some(typed)) = (tag.status(), typed_status(&token)?)
+            && typed.tier() != status.tier()
+        {
+            return Err(CommandError::WrongTier { name: status.name.clone(), tier: status.tier(), typed: typed.tier() });
+        }
+        if token.is_burned && !(matches!(token.polarity, Polarity::Positive) && modifier.can_be_burned()) {
+            return Err(CommandError::CantBurn(token.name));
+        }
+        match modifier {
+            Modifier::Tag(tag) => modifiers.add_tag(scope, tag, token.polarity, token.is_burned),
+            Modifier::Theme(theme) => modifiers.add_theme(scope, theme, token.polarity, token.is_burned),
+        }
+    }
+    Ok(modifiers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{might::Might, tag::Tag, theme::Theme};
+
+    fn hero() -> Hero {
+        let theme = Theme {
+            id: uuid::Uuid::new_v4(),
+            name: "Warrior".to_string(),
+            themebook: "Skills & Trades".to_string(),
+            power_tags: vec![Tag::new_power("Strong"), Tag::new_power("Agile")],
+            weakness_tags: vec![Tag::new_weakness("Clumsy")],
+            might: Might::Origin,
+            quest: String::new(),
+            improve: 0,
+            abandon: 0,
+            milestone: 0,
+            special_improvements: vec![],
+        };
+        Hero {
+            name: "Kara".to_string(),
+            player: "kara".to_string(),
+            themes: vec![theme],
+            backpack: vec![Tag::new_story("Rope")],
+            statuses: vec![Tag::new_status("Poisoned", 4).unwrap()],
+        }
+    }
+
+    fn scene() -> Scene {
+        Scene { name: "Docks".to_string(), story_tags: vec![Tag::new_story("Fog")], ..Scene::default() }
+    }
+
+    fn roll(input: &str) -> Result<ModifierMap, CommandError> {
+        parse_roll(input, Some(&hero()), &scene())
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Command::parse("/r +Strong"), Ok(Command::Roll { modifiers: "+Strong".to_string() }));
+        assert_eq!(Command::parse("/secret -Fog"), Ok(Command::SecretRoll { modifiers: "-Fog".to_string() }));
+        assert_eq!(
+            Command::parse("/w Nia  meet me at the docks "),
+            Ok(Command::Whisper { to: "Nia".to_string(), content: "meet me at the docks".to_string() })
+        );
+        assert_eq!(Command::parse("/me draws a sword"), Ok(Command::Me { content: "draws a sword".to_string() }));
+        assert_eq!(
+            Command::parse("/status Kara scared-2"),
+            Ok(Command::Status { hero: "Kara".to_string(), name: "scared".to_string(), tier: 2 })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        assert_eq!(Command::parse("/dance"), Err(CommandError::UnknownCommand("dance".to_string())));
+        assert!(matches!(Command::parse("/whisper Nia"), Err(CommandError::Usage(_))));
+        assert!(matches!(Command::parse("/me"), Err(CommandError::Usage(_))));
+        assert_eq!(Command::parse("/status Kara scared"), Err(CommandError::InvalidStatus("scared".to_string())));
+        assert_eq!(Command::parse("/status Kara scared-7"), Err(CommandError::Status(StatusError::InvalidTier(7))));
+    }
+
+    #[test]
+    fn resolves_names_ignoring_case() {
+        let modifiers = roll("+strong +rope +FOG -clumsy").unwrap();
+        assert_eq!(modifiers.to_string(), "+[Strong]+ +[Rope]+ +[Fog]+ -[Clumsy]-");
+        assert_eq!(modifiers.net_value(), 2);
+    }
+
+    #[test]
+    fn burns_with_a_bang_or_three_signs() {
+        assert_eq!(roll("+Agile!").unwrap().net_value(), 3);
+        assert_eq!(roll("+++[Agile]+++").unwrap().net_value(), 3);
+        assert_eq!(roll("+Agile!").unwrap().to_string(), "+++[Agile]+++");
+    }
+
+    #[test]
+    fn brackets_pick_the_kind_of_modifier() {
+        assert_eq!(roll("+<Warrior>").unwrap().net_value(), 1);
+        assert_eq!(roll("+[Warrior]").unwrap_err(), CommandError::UnknownTag("Warrior".to_string()));
+        assert_eq!(roll("-[Poisoned]").unwrap_err(), CommandError::UnknownTag("Poisoned".to_string()));
+        assert_eq!(roll("-{Poisoned}").unwrap().net_value(), -4);
+    }
+
+    #[test]
+    fn round_trips_what_modifiers_display() {
+        let modifiers = roll("+Strong +Agile! -Clumsy -{Poisoned:4} +<Warrior>").unwrap();
+        let displayed = modifiers.to_string();
+        let reparsed = roll(&displayed).unwrap();
+        assert_eq!(reparsed.to_string(), displayed);
+        assert_eq!(reparsed.net_value(), modifiers.net_value());
+    }
+
+    #[test]
+    fn status_tiers_must_match_the_sheet() {
+        assert_eq!(roll("-{Poisoned:4}").unwrap().net_value(), -4);
+        assert_eq!(roll("-{Poisoned:1}").unwrap_err(), CommandError::WrongTier { name: "Poisoned".to_string(), tier: 4, typed: 1 });
+        assert_eq!(roll("-{Poisoned:0}").unwrap_err(), CommandError::Status(StatusError::InvalidTier(0)));
+    }
+
+    #[test]
+    fn statuses_must_be_on_the_sheet() {
+        assert_eq!(roll("-{Scared:2,3}").unwrap_err(), CommandError::UnknownTag("Scared".to_string()));
+        assert_eq!(roll("+{Lucky:6}").unwrap_err(), CommandError::UnknownTag("Lucky".to_string()));
+    }
+
+    #[test]
+    fn rejects_bad_rolls() {
+        assert_eq!(roll("Strong").unwrap_err(), CommandError::MissingSign("Strong".to_string()));
+        assert_eq!(roll("+[Strong").unwrap_err(), CommandError::Unclosed("[Strong".to_string()));
+        assert_eq!(roll("+Sword").unwrap_err(), CommandError::UnknownTag("Sword".to_string()));
+        assert_eq!(roll("-Strong!").unwrap_err(), CommandError::CantBurn("Strong".to_string()));
+        assert_eq!(roll("+Clumsy!").unwrap_err(), CommandError::CantBurn("Clumsy".to_string()));
+        assert_eq!(roll("-{Scared:x}").unwrap_err(), CommandError::InvalidStatus("Scared:x".to_string()));
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tag::{TagDuration, TagRef, TagScope};
+
+/// Something the roller can spend power from a successful roll on.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum Effect {
+    /// Create a story tag in the scene, lasting as long as the roller chooses.
+    CreateStoryTag { name: String, #[serde(default)] duration: TagDuration },
+    /// Give a challenge or hero a status of the given tier.
+    InflictStatus { target: TagScope, name: String, tier: u8 },
+    /// Reduce a challenge's or hero's status by the given number of tiers.
+    ReduceStatus { status: TagRef, amount: u8 },
+    /// Learn a useful detail about the scene or a challenge.
+    Discover { detail: String },
+    /// Accomplish an additional feat alongside the main action.
+    ExtraFeat { description: String },
+}
+
+impl Effect {
+    /// Get the name of the challenge the effect targets, if it targets one.
+    pub fn challenge(&self) -> Option<&str> {
+        match self {
+            Effect::InflictStatus { target: TagScope::Challenge(name), .. } => Some(name),
+            Effect::ReduceStatus { status: TagRef { scope: TagScope::Challenge(name), .. }, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Get the power cost of the effect.
+    pub fn cost(&self) -> u8 {
+        match self {
+            Effect::CreateStoryTag { .. } => 2,
+            Effect::InflictStatus { tier, .. } => *tier,
+            Effect::ReduceStatus { amount, .. } => *amount,
+            Effect::Discover { .. } | Effect::ExtraFeat { .. } => 1,
+        }
+    }
+}
+
+/// Display the effect in a human-readable format.
+impl std::fmt::Display for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Effect::CreateStoryTag { name, duration: TagDuration::Permanent } => write!(f, "created [{name}]"),
+            Effect::CreateStoryTag { name, duration } => write!(f, "created [{name}] ({duration})"),
+            Effect::InflictStatus { target, name, tier } => write!(f, "gave {target} {{{name}:{tier}}}"),
+            Effect::ReduceStatus { status, amount } => write!(f, "reduced a status on {} by {amount}", status.scope),
+            Effect::Discover { detail } => write!(f, "discovered {detail}"),
+            Effect::ExtraFeat { description } => write!(f, "also {description}"),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Bytes below this are used for dice, so each face is equally likely; the rest are skipped.
+const UNBIASED_BELOW: u8 = 252;
+
+/// Everything needed to check a fair roll: the server's seed, revealed after the roll, the hash of it
+/// the server published before the roll, and the entropy the roller's client added.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RollProof {
+    pub commitment: String,
+    pub server_seed: String,
+    pub client_seed: String,
+    /// The roll's id, or the threat's for a reaction, so the same seeds never give the same dice twice.
+    /// Secret rolls have no id and use 0.
+    pub nonce: u64,
+}
+
+/// Ways a fair roll can fail to check out.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FairnessError {
+    /// The revealed seed isn't the one the server committed to.
+    WrongSeed,
+    /// The dice aren't the ones the seeds give.
+    WrongDice { expected: (i8, i8) },
+}
+
+impl std::fmt::Display for FairnessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FairnessError::WrongSeed => write!(f, "the revealed seed doesn't match the server's commitment"),
+            FairnessError::WrongDice { expected: (d1, d2) } => write!(f, "the seeds give ({d1}, {d2}), not the dice rolled"),
+        }
+    }
+}
+
+/// Get the commitment to a server seed: the hex SHA-256 hash of it, as `echo -n <seed> | sha256sum` gives.
+pub fn commitment(server_seed: &str) -> String {
+    hex::encode(Sha256::digest(server_seed.as_bytes()))
+}
+
+/// Get the dice a fair roll comes to. Each die is taken from the next usable byte of the SHA-256 hash
+/// of `server_seed:client_seed:nonce`, as `byte % 6 + 1`, skipping bytes of 252 and up and hashing
+/// the hash again if it runs out.
+pub fn fair_dice(server_seed: &str, client_seed: &str, nonce: u64) -> (i8, i8) {
+    let mut hash = Sha256::digest(format!("{server_seed}:{client_seed}:{nonce}").as_bytes());
+    let mut dice = vec![];
+    loop {
+        dice.extend(hash.iter().filter(|byte| **byte < UNBIASED_BELOW).map(|byte| (byte % 6 + 1) as i8));
+        if let [d1, d2, ..] = dice[..] {
+            return (d1, d2);
+        }
+        hash = Sha256::digest(hash);
+    }
+}
+
+impl RollProof {
+    /// Check the revealed seed matches the commitment and gives the dice that were rolled.
+    /// Whether the commitment was published before the roll is up to whoever is checking.
+    pub fn verify(&self, dice_values: (i8, i8)) -> Result<(), FairnessError> {
+        if commitment(&self.server_seed) != self.commitment {
+            return Err(FairnessError::WrongSeed);
+        }
+        let expected = fair_dice(&self.server_seed, &self.client_seed, self.nonce);
+        if expected != dice_values {
+            return Err(FairnessError::WrongDice { expected });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proof() -> RollProof {
+        RollProof { commitment: commitment("server"), server_seed: "server".to_string(), client_seed: "client".to_string(), nonce: 1 }
+    }
+
+    #[test]
+    fn commitment_is_the_hex_sha256_of_the_seed() {
+        assert_eq!(commitment("server"), "b3eacd33433b31b5252351032c9b3e7a2e7aa7738d5decdf0dd6c62680853c06");
+    }
+
+    #[test]
+    fn fair_dice_depend_on_every_input() {
+        assert_eq!(fair_dice("server", "client", 1), (5, 5));
+        assert_eq!(fair_dice("server", "client", 2), (5, 2));
+        assert_eq!(fair_dice("server", "client", 1), fair_dice("server", "client", 1));
+    }
+
+    #[test]
+    fn fair_dice_stay_between_one_and_six() {
+        for nonce in 0..1000 {
+            let (d1, d2) = fair_dice("server", "client", nonce);
+            assert!((1..=6).contains(&d1) && (1..=6).contains(&d2));
+        }
+    }
+
+    #[test]
+    fn verify_accepts_a_valid_proof() {
+        assert_eq!(proof().verify((5, 5)), Ok(()));
+    }
+
+    #[test]
+    fn verify_rejects_a_swapped_seed() {
+        let proof = RollProof { server_seed: "other".to_string(), ..proof() };
+        assert_eq!(proof.verify(fair_dice("other", "client", 1)), Err(FairnessError::WrongSeed));
+    }
+
+    #[test]
+    fn verify_rejects_tampered_dice() {
+        assert_eq!(proof().verify((6, 6)), Err(FairnessError::WrongDice { expected: (5, 5) }));
+    }
+
+    #[test]
+    fn verify_rejects_a_changed_client_seed_or_nonce() {
+        assert!(matches!(RollProof { client_seed: "mine".to_string(), ..proof() }.verify((5, 5)), Err(FairnessError::WrongDice { .. })));
+        assert!(matches!(RollProof { nonce: 2, ..proof() }.verify((5, 5)), Err(FairnessError::WrongDice { .. })));
+    }
+}
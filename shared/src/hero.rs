@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::{theme::Theme};
+use crate::{status::{StatusError, MAX_TIER}, theme::Theme};
 
-use super::{tag::Tag};
+use super::{tag::{self, Tag, TagDuration}};
+
+/// The status tier at which a hero is taken out of the scene.
+pub const TAKEN_OUT_TIER: u8 = MAX_TIER;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Hero {
@@ -10,5 +14,77 @@ pub struct Hero {
     pub player: String,
     pub themes: Vec<Theme>,
     pub backpack: Vec<Tag>,
+    #[serde(default)]
+    pub statuses: Vec<Tag>,
+}
+
+/// A change the narrator can make to one of a hero's statuses.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum StatusAction {
+    /// Give the hero a status, stacking onto an existing status of the same name.
+    Inflict { name: String, tier: u8 },
+    /// Reduce a status by the given number of tiers.
+    Reduce { status: Uuid, amount: u8 },
+    /// Remove a status entirely.
+    Clear { status: Uuid },
+}
+
+/// Something a player can do with the story tags in a hero's backpack.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum BackpackAction {
+    /// Add a new story tag with the given name and duration to the backpack.
+    Add { name: String, #[serde(default)] duration: TagDuration },
+    /// Remove a story tag from the backpack.
+    Remove { tag: Uuid },
+    /// Scratch a story tag in the backpack.
+    Scratch { tag: Uuid },
+    /// Give a story tag to another hero.
+    Transfer { tag: Uuid, to: String },
+    /// Drop a story tag into the scene.
+    Drop { tag: Uuid },
+    /// Pick up a story tag from the scene.
+    PickUp { tag: Uuid },
 }
 
+impl BackpackAction {
+    /// Describe the action in a human-readable format, given the name of the tag it affects.
+    pub fn describe(&self, tag_name: &str) -> String {
+        match self {
+            BackpackAction::Add { .. } => format!("packs [{tag_name}]"),
+            BackpackAction::Remove { .. } => format!("throws away [{tag_name}]"),
+            BackpackAction::Scratch { .. } => format!("scratches [{tag_name}]"),
+            BackpackAction::Transfer { to, .. } => format!("gives [{tag_name}] to {to}"),
+            BackpackAction::Drop { .. } => format!("drops [{tag_name}]"),
+            BackpackAction::PickUp { .. } => format!("picks up [{tag_name}]"),
+        }
+    }
+}
+
+impl Hero {
+    /// Get the status with the given id.
+    pub fn status(&self, id: Uuid) -> Option<&Tag> {
+        self.statuses.iter().find(|status| status.id() == id)
+    }
+
+    /// Apply a status action to the hero. Returns false if it targets a status the hero doesn't have,
+    /// or an error if the status can't be inflicted.
+    pub fn apply_status_action(&mut self, action: &StatusAction) -> Result<bool, StatusError> {
+        match action {
+            StatusAction::Inflict { name, tier } => {
+                tag::inflict_status(&mut self.statuses, name, *tier)?;
+                Ok(true)
+            }
+            StatusAction::Reduce { status, amount } => Ok(tag::reduce_status(&mut self.statuses, *status, *amount)),
+            StatusAction::Clear { status } => {
+                let before = self.statuses.len();
+                self.statuses.retain(|existing| existing.id() != *status);
+                Ok(self.statuses.len() != before)
+            }
+        }
+    }
+
+    /// Get the status that has taken the hero out, if any has reached the hero's limit.
+    pub fn taken_out_by(&self) -> Option<&Tag> {
+        self.statuses.iter().find(|status| status.get_value() >= TAKEN_OUT_TIER)
+    }
+}
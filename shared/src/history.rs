@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{hero::StatusAction, messaging::SystemRequest};
+
+/// A state-changing action someone took in the room, for the audit trail and undo.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct AuditEntry {
+    pub id: u64,
+    /// When the action was taken, in seconds since the Unix epoch.
+    pub at: u64,
+    pub username: String,
+    pub role: String,
+    pub action: String,
+    pub undone: bool,
+    /// Whether only the narrator may undo the action, as with dice rolls.
+    #[serde(default)]
+    pub narrator_only: bool,
+}
+
+impl AuditEntry {
+    /// Whether a user may undo or redo the action. Players can only undo their own actions, and never rolls;
+    /// the narrator can undo anything.
+    pub fn can_be_undone_by(&self, username: &str, role: &str) -> bool {
+        role == "narrator" || (!self.narrator_only && self.username == username)
+    }
+}
+
+/// Display the entry in a human-readable format, such as "Kara rolled".
+impl std::fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.username, self.action)
+    }
+}
+
+impl SystemRequest {
+    /// Describe what the request does to the room, or `None` if it doesn't change anything that could be undone.
+    /// Descriptions are seen by everyone, so they don't name challenges the players might not know about.
+    pub fn audit_description(&self) -> Option<String> {
+        let description = match self {
+            SystemRequest::Roll { .. } => "rolled".to_string(),
+            SystemRequest::Threat { hero, status, tier, .. } => format!("threatened {hero} with {{{status}:{tier}}}"),
+            SystemRequest::ReactionRoll { .. } => "reacted to a threat".to_string(),
+            SystemRequest::SpendPower { .. } => "spent power".to_string(),
+            SystemRequest::SceneUpdate { .. } => "updated the scene".to_string(),
+            SystemRequest::EndScene { .. } => "ended the scene".to_string(),
+            SystemRequest::EndSession { .. } => "ended the session".to_string(),
+            SystemRequest::HeroUpdate { hero, .. } => format!("updated {}", hero.name),
+            SystemRequest::HeroStatus { hero, action, .. } => match action {
+                StatusAction::Inflict { name, tier } => format!("gave {hero} {{{name}:{tier}}}"),
+                StatusAction::Reduce { amount, .. } => format!("reduced one of {hero}'s statuses by {amount}"),
+                StatusAction::Clear { .. } => format!("cleared one of {hero}'s statuses"),
+            },
+            SystemRequest::Backpack { hero, .. } => format!("changed {hero}'s backpack"),
+            SystemRequest::ChallengeUpdate { .. } => "updated a challenge".to_string(),
+            SystemRequest::Reveal { .. } => "changed what the players can see".to_string(),
+            SystemRequest::RoomSettings { .. } => "changed the room settings".to_string(),
+            SystemRequest::OpenCamp { name, .. } => format!("opened {name}"),
+            SystemRequest::CampAction { hero, .. } => format!("took a camp action for {hero}"),
+            SystemRequest::CloseCamp { .. } => "closed the camp".to_string(),
+            _ => return None,
+        };
+        Some(description)
+    }
+
+    /// Whether the request rolls dice. Only the narrator can undo a roll, so players can't roll again until they like the result.
+    pub fn rolls_dice(&self) -> bool {
+        matches!(self, SystemRequest::Roll { .. } | SystemRequest::ReactionRoll { .. })
+    }
+}
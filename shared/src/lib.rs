@@ -8,4 +8,19 @@ pub mod limit;
 pub mod scene;
 pub mod might;
 pub mod modifier;
-pub mod theme;
\ No newline at end of file
+pub mod theme;
+pub mod effect;
+pub mod roll;
+pub mod settings;
+pub mod camp;
+pub mod command;
+pub mod status;
+pub mod themebook;
+pub mod sheet;
+pub mod print;
+pub mod presence;
+pub mod visibility;
+pub mod log;
+pub mod history;
+pub mod probability;
+pub mod fairness;
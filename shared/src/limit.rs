@@ -1,8 +1,51 @@
 use serde::{Deserialize, Serialize};
 
+use crate::visibility::Visibility;
+
+/// What happens to a challenge when one of its limits is reached.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum LimitOutcome {
+    #[default]
+    Defeated,
+    Transformed,
+    Escaped,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Limit {
     pub name: String,
     pub progress: u8,
     pub max: u8,
-}
\ No newline at end of file
+    /// Names of the statuses that count towards this limit, in addition to statuses named after the limit itself.
+    #[serde(default)]
+    pub statuses: Vec<String>,
+    #[serde(default)]
+    pub outcome: LimitOutcome,
+    #[serde(default)]
+    pub visibility: Visibility,
+}
+
+impl Limit {
+    /// Create a new limit with the given name and maximum.
+    pub fn new(name: &str, max: u8, outcome: LimitOutcome) -> Self {
+        Limit {
+            name: name.to_string(),
+            progress: 0,
+            max,
+            statuses: vec![],
+            outcome,
+            visibility: Visibility::default(),
+        }
+    }
+
+    /// Whether a status with the given name counts towards this limit.
+    pub fn matches(&self, status_name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(status_name)
+            || self.statuses.iter().any(|name| name.eq_ignore_ascii_case(status_name))
+    }
+
+    /// Whether the limit has been reached.
+    pub fn is_reached(&self) -> bool {
+        self.progress >= self.max
+    }
+}
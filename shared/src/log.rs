@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{messaging::SystemResponse, roll::OutcomeDetails, tag::ExpiryReason};
+
+/// The formats a session log can be exported in.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    #[serde(alias = "md")]
+    Markdown,
+    Json,
+}
+
+impl LogFormat {
+    /// Get the file extension for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LogFormat::Markdown => "md",
+            LogFormat::Json => "json",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            LogFormat::Markdown => "text/markdown; charset=utf-8",
+            LogFormat::Json => "application/json",
+        }
+    }
+}
+
+/// Something that happened in the room, and when, in seconds since the Unix epoch.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogEntry {
+    pub at: u64,
+    pub response: SystemResponse,
+}
+
+/// The chronological record of what happened in a room, for writing up recaps.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SessionLog {
+    pub campaign: String,
+    pub entries: Vec<LogEntry>,
+}
+
+impl SessionLog {
+    pub fn new(campaign: &str) -> Self {
+        SessionLog { campaign: campaign.to_string(), entries: vec![] }
+    }
+
+    /// Record a response sent to everyone, if it's part of the story. Scene updates are only
+    /// recorded when the scene changes, not every time a tag in it does.
+    pub fn record(&mut self, at: u64, response: &SystemResponse) {
+        let is_logged = match response {
+            SystemResponse::SceneUpdate { scene } => self.current_scene() != scene.name,
+            response => is_story(response),
+        };
+        if is_logged {
+            self.entries.push(LogEntry { at, response: response.clone() });
+        }
+    }
+
+    /// Get the name of the scene the log is in, which is blank until the first scene starts.
+    fn current_scene(&self) -> &str {
+        self.entries
+            .iter()
+            .rev()
+            .find_map(|entry| match &entry.response {
+                SystemResponse::SceneUpdate { scene } => Some(scene.name.as_str()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the log as someone with the given role should see it, leaving out anything hidden from them.
+    pub fn visible_to(&self, role: &str) -> SessionLog {
+        let entries = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let response = entry.response.clone().visible_to(role)?;
+                Some(LogEntry { at: entry.at, response })
+            })
+            .collect();
+        SessionLog { campaign: self.campaign.clone(), entries }
+    }
+
+    /// Write the log out in the given format.
+    pub fn export(&self, format: LogFormat) -> Result<String, serde_json::Error> {
+        match format {
+            LogFormat::Markdown => Ok(self.to_markdown()),
+            LogFormat::Json => serde_json::to_string_pretty(self),
+        }
+    }
+
+    /// Write the log as Markdown, with a heading for each scene and a line for everything else.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\nTimes are UTC.\n", self.campaign);
+        for entry in &self.entries {
+            match &entry.response {
+                SystemResponse::SceneUpdate { scene } => markdown.push_str(&format!("\n## {}\n\n", scene.name)),
+                response => {
+                    if let Some(line) = describe(response) {
+                        markdown.push_str(&format!("- `{}` {line}\n", time_of_day(entry.at)));
+                    }
+                }
+            }
+        }
+        markdown
+    }
+}
+
+/// Whether a response is part of the story, rather than bookkeeping like presence or errors.
+fn is_story(response: &SystemResponse) -> bool {
+    matches!(
+        response,
+        SystemResponse::Chat { .. }
+            | SystemResponse::Emote { .. }
+            | SystemResponse::Roll { .. }
+            | SystemResponse::ReactionRoll { .. }
+            | SystemResponse::PowerSpent { .. }
+            | SystemResponse::Threat { .. }
+            | SystemResponse::StatusChanged { .. }
+            | SystemResponse::HeroTakenOut { .. }
+            | SystemResponse::Revealed { .. }
+            | SystemResponse::LimitReached { .. }
+            | SystemResponse::LootDropped { .. }
+            | SystemResponse::TagsExpired { .. }
+            | SystemResponse::BackpackChanged { .. }
+            | SystemResponse::CampOpened { .. }
+            | SystemResponse::CampActionTaken { .. }
+            | SystemResponse::CampClosed {}
+            | SystemResponse::Undone { .. }
+            | SystemResponse::Redone { .. }
+    )
+}
+
+/// Format seconds since the Unix epoch as the time of day, like `21:07`.
+pub fn time_of_day(at: u64) -> String {
+    format!("{:02}:{:02}", at / 3600 % 24, at / 60 % 60)
+}
+
+/// Describe the outcome of a roll, with any power it gives.
+fn describe_outcome(details: &OutcomeDetails) -> String {
+    match details {
+        OutcomeDetails::Detailed { outcome, power } if *power > 0 => format!("{outcome} with {power} power"),
+        details => details.outcome().to_string(),
+    }
+}
+
+/// Describe a response as a line of Markdown.
+fn describe(response: &SystemResponse) -> Option<String> {
+    let line = match response {
+        SystemResponse::Chat { username, content, .. } => format!("**{username}**: {content}"),
+        SystemResponse::Emote { username, content } => format!("*{username} {content}*"),
+        SystemResponse::Roll { dice_values: (d1, d2), username, modifiers, total, details, .. } => {
+            let modifiers = modifiers.to_string();
+            let modifiers = if modifiers.is_empty() { String::new() } else { format!(" {modifiers}") };
+            format!("**{username}** rolled ({d1}, {d2}){modifiers} = {total}: {}", describe_outcome(details))
+        }
+        SystemResponse::ReactionRoll { dice_values: (d1, d2), username, total, outcome, status, reduced_by, applied_tier, .. } => {
+            format!("**{username}** reacted ({d1}, {d2}) = {total}: {outcome}, {{{status}}} reduced by {reduced_by} to tier {applied_tier}")
+        }
+        SystemResponse::PowerSpent { username, effects, remaining, .. } => {
+            let effects = effects.iter().map(|effect| effect.to_string()).collect::<Vec<_>>().join(", ");
+            format!("**{username}** spent power: {effects} ({remaining} left)")
+        }
+        SystemResponse::Threat { hero, status, tier, description, .. } => {
+            format!("**{hero}** is threatened with {{{status}:{tier}}}: {description}")
+        }
+        SystemResponse::StatusChanged { hero, description } => format!("**{hero}** {description}"),
+        SystemResponse::HeroTakenOut { hero, status } => format!("**{hero}** is taken out by {}", status.name()),
+        SystemResponse::Revealed { description, .. } => format!("*{description}*"),
+        SystemResponse::LimitReached { challenge, limit, .. } => format!("**{challenge}** reached its {} limit", limit.name),
+        SystemResponse::LootDropped { challenge, tags, .. } => {
+            let tags = tags.iter().map(|tag| format!("[{tag}]")).collect::<Vec<_>>().join(" ");
+            format!("**{challenge}** dropped {tags}")
+        }
+        SystemResponse::TagsExpired { reason, tags, .. } => {
+            let when = match reason {
+                ExpiryReason::Used => "after being used",
+                ExpiryReason::SceneEnded => "as the scene ends",
+                ExpiryReason::SessionEnded => "as the session ends",
+            };
+            let tags = tags.iter().map(|(scope, tag)| format!("[{}] ({scope})", tag.name())).collect::<Vec<_>>().join(" ");
+            format!("{tags} expired {when}")
+        }
+        SystemResponse::BackpackChanged { hero, description, .. } => format!("**{hero}** {description}"),
+        SystemResponse::CampOpened { name } => format!("*{name} begins*"),
+        SystemResponse::CampActionTaken { hero, description, .. } => format!("**{hero}** {description}"),
+        SystemResponse::CampClosed {} => "*The camp ends*".to_string(),
+        SystemResponse::Undone { entry, by } => format!("*{by} undid: {entry}*"),
+        SystemResponse::Redone { entry, by } => format!("*{by} redid: {entry}*"),
+        _ => return None,
+    };
+    Some(line)
+}
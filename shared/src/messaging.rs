@@ -1,24 +1,150 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::modifier::ModifierMap;
+use crate::{camp::CampAction, challenge::Challenge, effect::Effect, fairness::RollProof, hero::{BackpackAction, Hero, StatusAction}, history::AuditEntry, limit::Limit, modifier::ModifierMap, presence::Member, roll::{OutcomeDetails, RollOutcome}, scene::Scene, settings::RoomSettings, tag::{ExpiryReason, Tag, TagScope}, visibility::{Secret, Visibility}};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum SystemResponse {
     Chat {username: String, role: String, content: String},
-    Roll {dice_values: (i8, i8), username: String, modifiers: ModifierMap, total: i8},
-    SceneUpdate {},
-    HeroUpdate {},
-    ChallengeUpdate {},
+    Emote {username: String, content: String},
+    Whisper {from: String, to: String, content: String},
+    Joined {username: String, role: String},
+    Left {username: String},
+    Roster {members: Vec<Member>},
+    Resync {settings: RoomSettings, scene: Scene, heroes: Vec<Hero>, members: Vec<Member>, missed: u64},
+    Typing {username: String, is_typing: bool},
+    Idle {username: String, is_idle: bool},
+    Roll {roll_id: u64, dice_values: (i8, i8), username: String, modifiers: ModifierMap, total: i8, details: OutcomeDetails, #[serde(default)] proof: Option<RollProof>},
+    RollCommitment {username: String, commitment: String},
+    SecretRoll {dice_values: (i8, i8), username: String, modifiers: ModifierMap, total: i8, details: OutcomeDetails, #[serde(default)] proof: Option<RollProof>},
+    Threat {threat_id: u64, hero: String, player: String, status: String, tier: u8, description: String},
+    ReactionRoll {threat_id: u64, dice_values: (i8, i8), username: String, modifiers: ModifierMap, total: i8, outcome: RollOutcome, hero: String, status: String, reduced_by: u8, applied_tier: u8, #[serde(default)] proof: Option<RollProof>},
+    PowerSpent {username: String, roll_id: u64, effects: Vec<Effect>, remaining: u8, #[serde(default)] challenge_visibility: HashMap<String, Visibility>},
+    SceneUpdate {scene: Scene},
+    HeroUpdate {hero: Hero},
+    StatusChanged {hero: String, description: String},
+    HeroTakenOut {hero: String, status: Tag},
+    ChallengeUpdate {challenge: Challenge},
+    Revealed {secret: Secret, visibility: Visibility, description: String, #[serde(default)] challenge_visibility: Visibility},
+    LimitReached {challenge: String, limit: Limit, #[serde(default)] challenge_visibility: Visibility},
+    LootDropped {challenge: String, tags: Vec<String>, #[serde(default)] challenge_visibility: Visibility},
+    TagsExpired {reason: ExpiryReason, tags: Vec<(TagScope, Tag)>, #[serde(default)] tag_visibility: HashMap<Uuid, Visibility>},
+    BackpackChanged {hero: String, action: BackpackAction, description: String},
     FellowshipUpdate {},
+    RoomSettings {settings: RoomSettings},
+    CampOpened {name: String},
+    CampActionTaken {hero: String, action: CampAction, description: String},
+    CampClosed {},
+    History {entries: Vec<AuditEntry>},
+    Undone {entry: AuditEntry, by: String},
+    Redone {entry: AuditEntry, by: String},
+    StateRestored {settings: RoomSettings, scene: Scene, heroes: Vec<Hero>},
+    LimitExceeded {limit: LimitExceeded},
+    Error {message: String},
+}
+
+/// A limit the server puts on each connection, sent back when a request breaks it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum LimitExceeded {
+    TooManyChats {retry_after_ms: u64},
+    TooManyRolls {retry_after_ms: u64},
+    MessageTooLarge {size: usize, max: usize},
+    ContentTooLong {length: usize, max: usize},
+}
+
+/// Display the limit in a human-readable format.
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitExceeded::TooManyChats { retry_after_ms } => {
+                write!(f, "you're sending messages too quickly, try again in {:.1}s", *retry_after_ms as f64 / 1000.0)
+            }
+            LimitExceeded::TooManyRolls { retry_after_ms } => {
+                write!(f, "you're rolling too quickly, try again in {:.1}s", *retry_after_ms as f64 / 1000.0)
+            }
+            LimitExceeded::MessageTooLarge { size, max } => write!(f, "that request is {size} bytes, the limit is {max}"),
+            LimitExceeded::ContentTooLong { length, max } => write!(f, "that message is {length} characters, the limit is {max}"),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum SystemRequest {
     Chat {username: String, role: String, content: String},
-    Roll {username: String, modifiers: ModifierMap},
-    SceneUpdate {},
-    HeroUpdate {},
-    ChallengeUpdate {},
+    Emote {username: String, content: String},
+    Whisper {username: String, to: String, content: String},
+    Typing {username: String, is_typing: bool},
+    Roll {username: String, modifiers: ModifierMap, #[serde(default)] client_seed: String},
+    SecretRoll {username: String, role: String, modifiers: ModifierMap, #[serde(default)] client_seed: String},
+    Threat {role: String, hero: String, status: String, tier: u8, description: String},
+    ReactionRoll {username: String, threat_id: u64, modifiers: ModifierMap, #[serde(default)] client_seed: String},
+    SpendPower {username: String, roll_id: u64, effects: Vec<Effect>},
+    SceneUpdate {role: String, scene: Scene},
+    EndScene {role: String},
+    EndSession {role: String},
+    HeroUpdate {username: String, role: String, hero: Hero},
+    HeroStatus {role: String, hero: String, action: StatusAction},
+    Backpack {username: String, hero: String, action: BackpackAction},
+    ChallengeUpdate {role: String, challenge: Challenge},
+    Reveal {role: String, secret: Secret, visibility: Visibility},
     FellowshipUpdate {},
+    RoomSettings {role: String, settings: RoomSettings},
+    OpenCamp {role: String, name: String},
+    CampAction {username: String, hero: String, action: CampAction},
+    CloseCamp {role: String},
+    Undo {},
+    Redo {},
 }
 
+impl SystemRequest {
+    /// Replace the username and role the client claimed with the ones from its session,
+    /// so nobody can act under another player's name or as the narrator.
+    pub fn authenticate(&mut self, session_username: &str, session_role: &str) {
+        match self {
+            SystemRequest::Chat { username, role, .. }
+            | SystemRequest::SecretRoll { username, role, .. }
+            | SystemRequest::HeroUpdate { username, role, .. } => {
+                *username = session_username.to_string();
+                *role = session_role.to_string();
+            }
+            SystemRequest::Emote { username, .. }
+            | SystemRequest::Whisper { username, .. }
+            | SystemRequest::Typing { username, .. }
+            | SystemRequest::Roll { username, .. }
+            | SystemRequest::ReactionRoll { username, .. }
+            | SystemRequest::SpendPower { username, .. }
+            | SystemRequest::Backpack { username, .. }
+            | SystemRequest::CampAction { username, .. } => *username = session_username.to_string(),
+            SystemRequest::Threat { role, .. }
+            | SystemRequest::SceneUpdate { role, .. }
+            | SystemRequest::EndScene { role }
+            | SystemRequest::EndSession { role }
+            | SystemRequest::HeroStatus { role, .. }
+            | SystemRequest::ChallengeUpdate { role, .. }
+            | SystemRequest::Reveal { role, .. }
+            | SystemRequest::RoomSettings { role, .. }
+            | SystemRequest::OpenCamp { role, .. }
+            | SystemRequest::CloseCamp { role } => *role = session_role.to_string(),
+            SystemRequest::FellowshipUpdate {} | SystemRequest::Undo {} | SystemRequest::Redo {} => (),
+        }
+    }
+}
+
+/// A request to join a campaign, sent over HTTP before opening the WebSocket.
+/// The passphrase decides whether the user joins as a player or as the narrator.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LoginRequest {
+    pub campaign: String,
+    pub username: String,
+    pub passphrase: String,
+}
+
+/// A successful login, with the token to open the WebSocket with.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub username: String,
+    pub role: String,
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::{tag::Tag, theme::Theme};
+use crate::{tag::{Tag, TagRef, TagScope}, theme::Theme};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum Modifier {
@@ -15,6 +16,31 @@ impl Modifier {
             Modifier::Theme(theme) => theme.get_value(),
         }
     }
+
+    /// Get the stable id of the tag or theme.
+    pub fn id(&self) -> Uuid {
+        match self {
+            Modifier::Tag(tag) => tag.id(),
+            Modifier::Theme(theme) => theme.id,
+        }
+    }
+
+    /// Get the display name of the tag or theme.
+    pub fn name(&self) -> &str {
+        match self {
+            Modifier::Tag(tag) => tag.name(),
+            Modifier::Theme(theme) => &theme.name,
+        }
+    }
+
+    /// Whether the modifier can be burned: only unscratched power and story tags, and themes, can.
+    pub fn can_be_burned(&self) -> bool {
+        match self {
+            Modifier::Tag(tag @ (Tag::Power { .. } | Tag::Story { .. })) => tag.get_value() > 0,
+            Modifier::Tag(_) => false,
+            Modifier::Theme(_) => true,
+        }
+    }
 }
 
 /// The polarity of a tag, indicating whether it has a positive or negative effect.
@@ -24,61 +50,122 @@ pub enum Polarity {
     Negative,
 }
 
+/// A modifier in a roll, along with where it comes from, its polarity and whether it is burned.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ModifierEntry {
+    scope: TagScope,
+    modifier: Modifier,
+    polarity: Polarity,
+    is_burned: bool,
+}
+
+impl ModifierEntry {
+    fn tag_ref(&self) -> TagRef {
+        TagRef::new(self.scope.clone(), self.modifier.id())
+    }
+}
+
 /// A collection of tags associated with a character, along with their polarities and whether they are burned.
+/// Modifiers are identified by their scope and id, so two different tags with the same name can both be used.
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct ModifierMap {
-    modifiers: std::collections::HashMap<String, (Modifier, Polarity, bool)>,
+    modifiers: Vec<ModifierEntry>,
 }
 
 impl ModifierMap {
+    /// Add a modifier, replacing any existing modifier with the same scope and id.
+    fn insert(&mut self, entry: ModifierEntry) {
+        let tag_ref = entry.tag_ref();
+        match self.modifiers.iter_mut().find(|existing| existing.tag_ref() == tag_ref) {
+            Some(existing) => *existing = entry,
+            None => self.modifiers.push(entry),
+        }
+    }
+
     /// Add a tag to the TagMap with the specified polarity and burned status.
-    pub fn add_tag(&mut self, tag: Tag, polarity: Polarity, is_burned: bool) {
-        let name = match &tag {
-            Tag::Power { name, .. }
-            | Tag::Weakness { name, .. }
-            | Tag::Story { name, .. }
-            | Tag::Status { name, .. } => name.clone(),
-        };
-        self.modifiers.insert(name, (Modifier::Tag(tag), polarity, is_burned));
+    pub fn add_tag(&mut self, scope: TagScope, tag: Tag, polarity: Polarity, is_burned: bool) {
+        self.insert(ModifierEntry { scope, modifier: Modifier::Tag(tag), polarity, is_burned });
+    }
+
+    pub fn add_theme(&mut self, scope: TagScope, theme: Theme, polarity: Polarity, is_burned: bool) {
+        self.insert(ModifierEntry { scope, modifier: Modifier::Theme(theme), polarity, is_burned });
+    }
+
+    pub fn add_burned_tag(&mut self, scope: TagScope, tag: Tag) {
+        self.add_tag(scope, tag, Polarity::Positive, true);
+    }
+
+    pub fn add_positive_tag(&mut self, scope: TagScope, tag: Tag) {
+        self.add_tag(scope, tag, Polarity::Positive, false);
     }
 
-    pub fn add_theme(&mut self, theme: Theme, polarity: Polarity, is_burned: bool) {
-        let name = theme.name.to_string();
-        self.modifiers.insert(name, (Modifier::Theme(theme), polarity, is_burned));
+    pub fn add_burned_theme(&mut self, scope: TagScope, theme: Theme) {
+        self.add_theme(scope, theme, Polarity::Positive, true);
     }
 
-    pub fn add_burned_tag(&mut self, tag: Tag) {
-        self.add_tag(tag, Polarity::Positive, true);
+    pub fn add_positive_theme(&mut self, scope: TagScope, theme: Theme) {
+        self.add_theme(scope, theme, Polarity::Positive, false);
     }
 
-    pub fn add_positive_tag(&mut self, tag: Tag) {
-        self.add_tag(tag, Polarity::Positive, false);
+    pub fn add_negative_tag(&mut self, scope: TagScope, tag: Tag) {
+        self.add_tag(scope, tag, Polarity::Negative, false);
     }
 
-    pub fn add_burned_theme(&mut self, theme: Theme) {
-        self.add_theme(theme, Polarity::Positive, true);
+    pub fn add_negative_theme(&mut self, scope: TagScope, theme: Theme) {
+        self.add_theme(scope, theme, Polarity::Negative, false);
     }
 
-    pub fn add_positive_theme(&mut self, theme: Theme) {
-        self.add_theme(theme, Polarity::Positive, false);
+    pub fn remove_modifier(&mut self, tag_ref: &TagRef) {
+        self.modifiers.retain(|entry| entry.tag_ref() != *tag_ref);
     }
 
-    pub fn add_negative_tag(&mut self, tag: Tag) {
-        self.add_tag(tag, Polarity::Negative, false);
+    /// Get references to every tag and theme used in the map.
+    pub fn tag_refs(&self) -> Vec<TagRef> {
+        self.modifiers.iter().map(ModifierEntry::tag_ref).collect()
     }
 
-    pub fn add_negative_theme(&mut self, theme: Theme) {
-        self.add_theme(theme, Polarity::Negative, false);
+    /// Get references to the tags and themes burned in the map.
+    pub fn burned(&self) -> Vec<TagRef> {
+        self.modifiers.iter().filter(|entry| entry.is_burned).map(ModifierEntry::tag_ref).collect()
     }
 
-    pub fn remove_modifier(&mut self, name: &str) {
-        self.modifiers.remove(name);
+    /// Replace each modifier with the one the lookup finds for its scope and id, keeping its polarity and
+    /// whether it is burned. Returns the first reference the lookup can't find.
+    pub fn resolve(&self, lookup: impl Fn(&TagRef) -> Option<Modifier>) -> Result<ModifierMap, TagRef> {
+        let modifiers = self
+            .modifiers
+            .iter()
+            .map(|entry| {
+                let tag_ref = entry.tag_ref();
+                let modifier = lookup(&tag_ref).ok_or(tag_ref)?;
+                Ok(ModifierEntry { modifier, ..entry.clone() })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ModifierMap { modifiers })
+    }
+
+    /// Get the net modifier to apply to a roll, tripling burned modifiers.
+    /// Summed in a wider type and clamped, since a roll can be sent with any number of modifiers.
+    pub fn net_value(&self) -> i8 {
+        let net = self
+            .get_modifiers()
+            .into_iter()
+            .map(|(modifier, polarity, is_burned)| {
+                let value = i32::from(modifier.get_value());
+                match polarity {
+                    Polarity::Positive if is_burned => value * 3,
+                    Polarity::Positive => value,
+                    Polarity::Negative => -value,
+                }
+            })
+            .fold(0i32, i32::saturating_add);
+        net.clamp(i32::from(i8::MIN), i32::from(i8::MAX)) as i8
     }
 
     pub fn get_modifiers(&self) -> Vec<(&Modifier, &Polarity, bool)> {
         self.modifiers
-            .values()
-            .map(|(modifier, polarity, is_burned)| (modifier, polarity, *is_burned))
+            .iter()
+            .map(|entry| (&entry.modifier, &entry.polarity, entry.is_burned))
             .collect()
     }
 }
@@ -89,7 +176,8 @@ impl std::fmt::Display for ModifierMap {
         let tags: Vec<String> = self
             .modifiers
             .iter()
-            .map(|(name, (modifier, polarity, is_burned))| {
+            .map(|ModifierEntry { modifier, polarity, is_burned, .. }| {
+                let name = modifier.name();
                 let sign = match polarity {
                     Polarity::Positive => "+",
                     Polarity::Negative => "-",
@@ -106,18 +194,22 @@ impl std::fmt::Display for ModifierMap {
                         Tag::Weakness { .. } => {
                             format!("{sign}[{}]{sign}", name)
                         }
-                        Tag::Status { name, tiers } => {
-                            let mut tiers: Vec<u8> = tiers.iter().cloned().collect();
-                            tiers.sort();
-                            let tier_str = tiers
-                                .iter()
+                        Tag::Status { status, .. } => {
+                            let tier_str = status
+                                .tiers()
                                 .map(|t| t.to_string())
                                 .collect::<Vec<String>>()
                                 .join(",");
-                            format!("{sign}{{{}:{}}}{sign}", name, tier_str)
+                            format!("{sign}{{{}:{}}}{sign}", status.name, tier_str)
                         }
                     },
-                    Modifier::Theme(theme) => todo!(),
+                    Modifier::Theme(_) => {
+                        if *is_burned {
+                            format!("{sign}{sign}{sign}<{}>{sign}{sign}{sign}", name)
+                        } else {
+                            format!("{sign}<{}>{sign}", name)
+                        }
+                    }
                 }
             })
             .collect();
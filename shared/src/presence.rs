@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Someone connected to a room, as shown in the roster.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Member {
+    pub username: String,
+    pub role: String,
+    pub is_idle: bool,
+}
@@ -0,0 +1,217 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+use crate::{challenge::Challenge, hero::Hero, limit::LimitOutcome, tag::Tag};
+
+/// A printable document, laid out as a title followed by headed sections of lines.
+#[derive(Clone, Debug)]
+pub struct PrintDocument {
+    pub title: String,
+    pub subtitle: String,
+    pub sections: Vec<PrintSection>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PrintSection {
+    pub heading: String,
+    pub lines: Vec<String>,
+}
+
+impl PrintSection {
+    fn new(heading: &str, lines: Vec<String>) -> Self {
+        PrintSection { heading: heading.to_string(), lines }
+    }
+}
+
+/// Lay out a hero sheet for printing.
+pub fn hero_sheet(hero: &Hero) -> PrintDocument {
+    let mut sections: Vec<PrintSection> = hero
+        .themes
+        .iter()
+        .map(|theme| {
+            let mut lines = vec![format!("{} ({:?})", theme.themebook, theme.might)];
+            lines.extend(theme.power_tags.iter().map(|tag| format!("Power: {}", tag_label(tag))));
+            lines.extend(theme.weakness_tags.iter().map(|tag| format!("Weakness: {}", tag_label(tag))));
+            lines.push(format!("Quest: {}", theme.quest));
+            lines.push(format!(
+                "Improve {}/3  Abandon {}/3  Milestone {}/3",
+                theme.improve, theme.abandon, theme.milestone
+            ));
+            lines.extend(theme.special_improvements.iter().map(|improvement| format!("Improvement: {improvement}")));
+            PrintSection::new(&theme.name, lines)
+        })
+        .collect();
+    sections.push(PrintSection::new("Backpack", hero.backpack.iter().map(tag_label).collect()));
+    sections.push(PrintSection::new("Statuses", hero.statuses.iter().map(tag_label).collect()));
+    PrintDocument {
+        title: hero.name.clone(),
+        subtitle: format!("Played by {}", hero.player),
+        sections,
+    }
+}
+
+/// Lay out a challenge card for printing.
+pub fn challenge_card(challenge: &Challenge) -> PrintDocument {
+    let limits = challenge
+        .limits
+        .iter()
+        .map(|limit| {
+            let outcome = match limit.outcome {
+                LimitOutcome::Defeated => "defeated",
+                LimitOutcome::Transformed => "transformed",
+                LimitOutcome::Escaped => "escapes",
+            };
+            format!("{} {}/{} ({outcome})", limit.name, limit.progress, limit.max)
+        })
+        .collect();
+    PrintDocument {
+        title: challenge.name.clone(),
+        subtitle: format!("Rating {}", challenge.rating),
+        sections: vec![
+            PrintSection::new("Limits", limits),
+            PrintSection::new("Tags", challenge.tags.iter().map(tag_label).collect()),
+            PrintSection::new("Statuses", challenge.statuses.iter().map(tag_label).collect()),
+            PrintSection::new("Threats", challenge.threats.clone()),
+            PrintSection::new("Loot", challenge.loot.clone()),
+        ],
+    }
+}
+
+/// Describe a tag for print, noting scratched tags and status tiers.
+fn tag_label(tag: &Tag) -> String {
+    match tag {
+        Tag::Power { name, is_scratched: true, .. } | Tag::Story { name, is_scratched: true, .. } => format!("{name} (scratched)"),
+        Tag::Status { status, .. } => status.label(),
+        _ => tag.name().to_string(),
+    }
+}
+
+impl PrintDocument {
+    /// Render the document as a standalone HTML page with its styles inlined.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape_html(&self.title)));
+        html.push_str(
+            "<style>\n\
+             body { font-family: Georgia, serif; max-width: 48em; margin: 2em auto; color: #222; }\n\
+             h1 { margin-bottom: 0; }\n\
+             .subtitle { color: #666; margin-top: 0.2em; }\n\
+             section { border: 1px solid #999; border-radius: 4px; padding: 0.5em 1em; margin: 1em 0; break-inside: avoid; }\n\
+             h2 { font-size: 1.2em; margin: 0.2em 0; }\n\
+             ul { margin: 0.2em 0; padding-left: 1.2em; }\n\
+             </style>\n",
+        );
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&format!("<h1>{}</h1>\n", escape_html(&self.title)));
+        html.push_str(&format!("<p class=\"subtitle\">{}</p>\n", escape_html(&self.subtitle)));
+        for section in &self.sections {
+            html.push_str(&format!("<section>\n<h2>{}</h2>\n<ul>\n", escape_html(&section.heading)));
+            for line in &section.lines {
+                html.push_str(&format!("<li>{}</li>\n", escape_html(line)));
+            }
+            html.push_str("</ul>\n</section>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Render the document as an A4 PDF using the standard Helvetica fonts, adding pages as needed.
+    pub fn to_pdf(&self) -> Vec<u8> {
+        const PAGE_WIDTH: f32 = 595.0;
+        const PAGE_HEIGHT: f32 = 842.0;
+        const MARGIN: f32 = 56.0;
+        const WRAP_AT: usize = 90;
+
+        // (font, size, indent, text) for every line of the document, in order
+        let mut lines: Vec<(&[u8], f32, f32, String)> = vec![
+            (b"F2", 22.0, 0.0, self.title.clone()),
+            (b"F1", 12.0, 0.0, self.subtitle.clone()),
+        ];
+        for section in &self.sections {
+            lines.push((b"F1", 8.0, 0.0, String::new()));
+            lines.push((b"F2", 14.0, 0.0, section.heading.clone()));
+            for line in &section.lines {
+                for wrapped in wrap(line, WRAP_AT) {
+                    lines.push((b"F1", 11.0, 12.0, wrapped));
+                }
+            }
+        }
+
+        let mut pages: Vec<Content> = vec![];
+        let mut y = 0.0;
+        for (font, size, indent, text) in lines {
+            let leading = size * 1.4;
+            if pages.is_empty() || y - leading < MARGIN {
+                pages.push(Content::new());
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            y -= leading;
+            let content = pages.last_mut().expect("a page was just added");
+            content.begin_text();
+            content.set_font(Name(font), size);
+            content.next_line(MARGIN + indent, y);
+            content.show(Str(&to_win_ansi(&text)));
+            content.end_text();
+        }
+
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let font_id = Ref::new(3);
+        let bold_font_id = Ref::new(4);
+        let page_ids: Vec<Ref> = (0..pages.len() as i32).map(|i| Ref::new(5 + i * 2)).collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(page_ids.len() as i32);
+        pdf.type1_font(font_id).base_font(Name(b"Helvetica")).encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold_font_id).base_font(Name(b"Helvetica-Bold")).encoding_predefined(Name(b"WinAnsiEncoding"));
+        for (page_id, content) in page_ids.into_iter().zip(pages) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            page.resources().fonts().pair(Name(b"F1"), font_id).pair(Name(b"F2"), bold_font_id);
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+        pdf.finish()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Split a line into lines of at most `width` characters, breaking between words.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let line = lines.last_mut().expect("there is always a line");
+        if !line.is_empty() && line.chars().count() + word.chars().count() + 1 > width {
+            lines.push(word.to_string());
+        } else {
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+    }
+    lines
+}
+
+/// Encode text for the standard PDF fonts, replacing characters they can't show.
+fn to_win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '\u{2018}' | '\u{2019}' => b'\'',
+            '\u{201c}' | '\u{201d}' => b'"',
+            '\u{2013}' | '\u{2014}' => b'-',
+            _ => b'?',
+        })
+        .collect()
+}
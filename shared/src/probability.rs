@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{modifier::ModifierMap, roll::RollOutcome};
+
+/// How many ways two six-sided dice can land.
+const OUTCOMES: u8 = 36;
+
+/// The chances of each outcome of a roll, as fractions between 0 and 1.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Odds {
+    pub success: f64,
+    pub mixed: f64,
+    pub miss: f64,
+}
+
+impl Odds {
+    /// Get the chances of each outcome of 2d6 plus a net modifier.
+    pub fn with_net(net: i8) -> Self {
+        let mut odds = Odds::default();
+        for (total, chance) in distribution(net) {
+            match RollOutcome::from_total(total) {
+                RollOutcome::Success => odds.success += chance,
+                RollOutcome::Mixed => odds.mixed += chance,
+                RollOutcome::Miss => odds.miss += chance,
+            }
+        }
+        odds
+    }
+
+    /// Get the chances of each outcome of a roll with the given modifiers.
+    pub fn of(modifiers: &ModifierMap) -> Self {
+        Odds::with_net(modifiers.net_value())
+    }
+}
+
+/// Display the odds as percentages, such as "success 42%, mixed 42%, miss 17%".
+impl std::fmt::Display for Odds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "success {:.0}%, mixed {:.0}%, miss {:.0}%",
+            self.success * 100.0,
+            self.mixed * 100.0,
+            self.miss * 100.0
+        )
+    }
+}
+
+/// Get each total 2d6 plus a net modifier can come to, with the chance of rolling it.
+pub fn distribution(net: i8) -> Vec<(i8, f64)> {
+    (2..=12i8)
+        .map(|dice| {
+            let ways = 6 - (dice - 7).abs();
+            (dice.saturating_add(net), f64::from(ways as u8) / f64::from(OUTCOMES))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check odds against counts out of the 36 ways two dice can land.
+    fn assert_odds(net: i8, success: u8, mixed: u8, miss: u8) {
+        let odds = Odds::with_net(net);
+        for (chance, ways) in [(odds.success, success), (odds.mixed, mixed), (odds.miss, miss)] {
+            assert!((chance - f64::from(ways) / 36.0).abs() < 1e-9, "{net:+}: {odds:?}");
+        }
+    }
+
+    #[test]
+    fn distribution_covers_every_way_the_dice_land() {
+        let plain = distribution(0);
+        assert_eq!(plain.len(), 11);
+        assert!((plain.iter().map(|(_, chance)| chance).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(plain[5], (7, 6.0 / 36.0));
+        assert_eq!(distribution(2)[0], (4, 1.0 / 36.0));
+    }
+
+    #[test]
+    fn odds_match_two_dice() {
+        assert_odds(0, 6, 15, 15);
+        assert_odds(1, 10, 16, 10);
+        assert_odds(2, 15, 15, 6);
+        assert_odds(3, 21, 12, 3);
+        assert_odds(-1, 3, 12, 21);
+        assert_odds(-2, 1, 9, 26);
+    }
+
+    #[test]
+    fn large_modifiers_settle_the_roll() {
+        assert_odds(8, 36, 0, 0);
+        assert_odds(-10, 0, 0, 36);
+        assert_odds(i8::MAX, 36, 0, 0);
+        assert_odds(i8::MIN, 0, 0, 36);
+    }
+
+    #[test]
+    fn odds_of_no_modifiers_are_plain_dice() {
+        assert_eq!(Odds::of(&ModifierMap::default()), Odds::with_net(0));
+        assert_eq!(Odds::with_net(0).to_string(), "success 17%, mixed 42%, miss 42%");
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+/// The outcome band a roll's total falls into.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum RollOutcome {
+    Success,
+    Mixed,
+    Miss,
+}
+
+impl RollOutcome {
+    /// Get the outcome for a roll total: 10+ is a success, 7-9 is mixed and 6 or less is a miss.
+    pub fn from_total(total: i8) -> Self {
+        match total {
+            10.. => RollOutcome::Success,
+            7..=9 => RollOutcome::Mixed,
+            _ => RollOutcome::Miss,
+        }
+    }
+}
+
+/// How a table resolves roll outcomes.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum OutcomeMode {
+    /// The narrator narrates the result of a success, mixed success or miss.
+    #[default]
+    Quick,
+    /// The roller spends power from the roll on specific effects.
+    Detailed,
+}
+
+/// The structured result of a roll, depending on the table's outcome mode.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum OutcomeDetails {
+    Quick { outcome: RollOutcome },
+    Detailed { outcome: RollOutcome, power: u8 },
+}
+
+impl OutcomeDetails {
+    pub fn outcome(&self) -> RollOutcome {
+        match self {
+            OutcomeDetails::Quick { outcome } | OutcomeDetails::Detailed { outcome, .. } => *outcome,
+        }
+    }
+
+    /// Get the power the roll yields to spend, which is always 0 for quick outcomes.
+    pub fn power(&self) -> u8 {
+        match self {
+            OutcomeDetails::Quick { .. } => 0,
+            OutcomeDetails::Detailed { power, .. } => *power,
+        }
+    }
+}
+
+impl RollOutcome {
+    /// Describe what the outcome means when resolved as a quick outcome.
+    pub fn quick_description(&self) -> &'static str {
+        match self {
+            RollOutcome::Success => "you do what you set out to do",
+            RollOutcome::Mixed => "you do it, but the narrator introduces a cost or complication",
+            RollOutcome::Miss => "the narrator decides what happens, and it's not in your favour",
+        }
+    }
+}
+
+/// Display the outcome in a human-readable format.
+impl std::fmt::Display for RollOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RollOutcome::Success => write!(f, "success"),
+            RollOutcome::Mixed => write!(f, "mixed success"),
+            RollOutcome::Miss => write!(f, "miss"),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{challenge::Challenge, tag::Tag, visibility::Visibility};
+
+/// The current scene, with its story tags and the challenges the heroes face.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Scene {
+    pub name: String,
+    pub story_tags: Vec<Tag>,
+    pub challenges: Vec<Challenge>,
+    /// How much of each story tag players can see, by id. Unlisted ones are revealed.
+    #[serde(default)]
+    pub tag_visibility: HashMap<Uuid, Visibility>,
+}
+
+impl Scene {
+    /// Get the challenge with the given name.
+    pub fn challenge(&self, name: &str) -> Option<&Challenge> {
+        self.challenges.iter().find(|challenge| challenge.name == name)
+    }
+
+    /// Get a mutable reference to the challenge with the given name.
+    pub fn challenge_mut(&mut self, name: &str) -> Option<&mut Challenge> {
+        self.challenges.iter_mut().find(|challenge| challenge.name == name)
+    }
+
+    /// Add a challenge to the scene, replacing any existing challenge with the same name.
+    pub fn upsert_challenge(&mut self, challenge: Challenge) {
+        match self.challenge_mut(&challenge.name) {
+            Some(existing) => *existing = challenge,
+            None => self.challenges.push(challenge),
+        }
+    }
+}
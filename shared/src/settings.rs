@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use crate::roll::OutcomeMode;
+
+/// Table settings the narrator chooses for a room.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RoomSettings {
+    pub outcome_mode: OutcomeMode,
+    /// Whether rolls are made from seeds players can check afterwards, rather than by the server alone.
+    #[serde(default)]
+    pub fair_rolls: bool,
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{challenge::Challenge, hero::Hero, status::Status, tag::Tag};
+
+/// The current version of the sheet file format.
+pub const SHEET_VERSION: u32 = 1;
+
+/// A hero sheet or challenge stat block that can be saved to and loaded from a file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Sheet {
+    Hero(Hero),
+    Challenge(Challenge),
+}
+
+/// A sheet wrapped with the version of the format it was written in.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SheetEnvelope {
+    pub version: u32,
+    pub sheet: Sheet,
+}
+
+/// The file formats sheets can be written in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SheetFormat {
+    Json,
+    Toml,
+}
+
+/// Reasons a sheet can't be imported or exported.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SheetError {
+    Parse(String),
+    MissingVersion,
+    UnsupportedVersion(u32),
+    Invalid(String),
+}
+
+impl std::fmt::Display for SheetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SheetError::Parse(err) => write!(f, "couldn't read the sheet: {err}"),
+            SheetError::MissingVersion => write!(f, "the sheet has no format version"),
+            SheetError::UnsupportedVersion(version) => {
+                write!(f, "the sheet is version {version}, but this release only reads versions 1 to {SHEET_VERSION}")
+            }
+            SheetError::Invalid(reason) => write!(f, "the sheet is invalid: {reason}"),
+        }
+    }
+}
+
+impl SheetFormat {
+    /// Get the file extension for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SheetFormat::Json => "json",
+            SheetFormat::Toml => "toml",
+        }
+    }
+
+    /// Get the format matching a file name's extension.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        match name.rsplit('.').next()?.to_ascii_lowercase().as_str() {
+            "json" => Some(SheetFormat::Json),
+            "toml" => Some(SheetFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+impl Sheet {
+    /// Get the name of the hero or challenge on the sheet.
+    pub fn name(&self) -> &str {
+        match self {
+            Sheet::Hero(hero) => &hero.name,
+            Sheet::Challenge(challenge) => &challenge.name,
+        }
+    }
+
+    /// Check the sheet describes a valid hero or challenge.
+    pub fn validate(&self) -> Result<(), SheetError> {
+        match self {
+            Sheet::Hero(hero) => validate_hero(hero),
+            Sheet::Challenge(challenge) => validate_challenge(challenge),
+        }
+    }
+}
+
+/// Write a sheet to a string in the given format, wrapped in a versioned envelope.
+pub fn export_sheet(sheet: &Sheet, format: SheetFormat) -> Result<String, SheetError> {
+    sheet.validate()?;
+    let envelope = SheetEnvelope { version: SHEET_VERSION, sheet: sheet.clone() };
+    match format {
+        SheetFormat::Json => serde_json::to_string_pretty(&envelope).map_err(|err| SheetError::Parse(err.to_string())),
+        SheetFormat::Toml => toml::to_string_pretty(&envelope).map_err(|err| SheetError::Parse(err.to_string())),
+    }
+}
+
+/// Read a sheet written in the given format by this or an earlier release, migrating and validating it.
+pub fn import_sheet(content: &str, format: SheetFormat) -> Result<Sheet, SheetError> {
+    let value: Value = match format {
+        SheetFormat::Json => serde_json::from_str(content).map_err(|err| SheetError::Parse(err.to_string()))?,
+        SheetFormat::Toml => toml::from_str(content).map_err(|err| SheetError::Parse(err.to_string()))?,
+    };
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(SheetError::MissingVersion)? as u32;
+    if version == 0 || version > SHEET_VERSION {
+        return Err(SheetError::UnsupportedVersion(version));
+    }
+    let envelope: SheetEnvelope =
+        serde_json::from_value(migrate(value, version)).map_err(|err| SheetError::Parse(err.to_string()))?;
+    envelope.sheet.validate()?;
+    Ok(envelope.sheet)
+}
+
+/// Steps upgrading a sheet from each older format version to the next, starting with version 1.
+const MIGRATIONS: &[fn(Value) -> Value] = &[];
+
+/// Upgrade a sheet from an older format version to the current one, one version at a time.
+fn migrate(mut value: Value, version: u32) -> Value {
+    for step in &MIGRATIONS[(version - 1) as usize..] {
+        value = step(value);
+    }
+    value["version"] = Value::from(SHEET_VERSION);
+    value
+}
+
+fn validate_hero(hero: &Hero) -> Result<(), SheetError> {
+    if hero.name.trim().is_empty() {
+        return Err(SheetError::Invalid("the hero has no name".into()));
+    }
+    for theme in &hero.themes {
+        if theme.name.trim().is_empty() {
+            return Err(SheetError::Invalid(format!("{} has a theme with no name", hero.name)));
+        }
+        if !theme.power_tags.iter().all(|tag| matches!(tag, Tag::Power { .. })) {
+            return Err(SheetError::Invalid(format!("{} has a power tag that isn't a power tag", theme.name)));
+        }
+        if !theme.weakness_tags.iter().all(|tag| matches!(tag, Tag::Weakness { .. })) {
+            return Err(SheetError::Invalid(format!("{} has a weakness tag that isn't a weakness tag", theme.name)));
+        }
+    }
+    validate_statuses(&hero.name, &hero.statuses)
+}
+
+fn validate_challenge(challenge: &Challenge) -> Result<(), SheetError> {
+    if challenge.name.trim().is_empty() {
+        return Err(SheetError::Invalid("the challenge has no name".into()));
+    }
+    if let Some(limit) = challenge.limits.iter().find(|limit| limit.max == 0) {
+        return Err(SheetError::Invalid(format!("{}'s {} limit has no maximum", challenge.name, limit.name)));
+    }
+    validate_statuses(&challenge.name, &challenge.statuses)
+}
+
+fn validate_statuses(owner: &str, statuses: &[Tag]) -> Result<(), SheetError> {
+    for status in statuses {
+        let Some(status) = status.status() else {
+            return Err(SheetError::Invalid(format!("{owner} has a status that isn't a status")));
+        };
+        if let Some(tier) = status.tiers().find(|&tier| Status::validate_tier(tier).is_err()) {
+            return Err(SheetError::Invalid(format!("{owner}'s {} status has tier {tier}, outside 1-6", status.name)));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// The highest status tier; a hero with a status at this tier is taken out.
+pub const MAX_TIER: u8 = 6;
+
+/// Human-readable names for each tier, indexed by tier.
+const TIER_NAMES: [&str; MAX_TIER as usize + 1] = ["Cleared", "Minor", "Troubling", "Serious", "Severe", "Critical", "Taken out"];
+
+/// Reasons a status can't be built or changed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusError {
+    InvalidTier(u8),
+    NameMismatch(String, String),
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusError::InvalidTier(tier) => write!(f, "status tiers must be between 1 and {MAX_TIER}, not {tier}"),
+            StatusError::NameMismatch(a, b) => write!(f, "can't merge {a} into {b}, they are different statuses"),
+        }
+    }
+}
+
+/// A status such as poisoned or scared, made up of marked tier boxes from 1 to 6.
+/// The status counts as its highest marked tier.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "UncheckedStatus")]
+pub struct Status {
+    pub name: String,
+    tiers: BTreeSet<u8>,
+}
+
+/// A status as it was sent, before its tiers have been checked.
+#[derive(Deserialize)]
+struct UncheckedStatus {
+    name: String,
+    tiers: BTreeSet<u8>,
+}
+
+impl TryFrom<UncheckedStatus> for Status {
+    type Error = StatusError;
+
+    fn try_from(status: UncheckedStatus) -> Result<Self, Self::Error> {
+        for &tier in &status.tiers {
+            Status::validate_tier(tier)?;
+        }
+        Ok(Status { name: status.name, tiers: status.tiers })
+    }
+}
+
+impl Status {
+    /// Create a status with a single tier marked.
+    pub fn new(name: &str, tier: u8) -> Result<Self, StatusError> {
+        let mut status = Status { name: name.to_string(), tiers: BTreeSet::new() };
+        status.add_tier(tier)?;
+        Ok(status)
+    }
+
+    /// Check a tier is between 1 and 6.
+    pub fn validate_tier(tier: u8) -> Result<u8, StatusError> {
+        if (1..=MAX_TIER).contains(&tier) {
+            Ok(tier)
+        } else {
+            Err(StatusError::InvalidTier(tier))
+        }
+    }
+
+    /// Get the current tier, which is the highest marked tier, or 0 once cleared.
+    pub fn tier(&self) -> u8 {
+        self.tiers.last().copied().unwrap_or(0)
+    }
+
+    /// Get the marked tiers, lowest first.
+    pub fn tiers(&self) -> impl Iterator<Item = u8> + '_ {
+        self.tiers.iter().copied()
+    }
+
+    /// Whether no tiers are marked.
+    pub fn is_cleared(&self) -> bool {
+        self.tiers.is_empty()
+    }
+
+    /// Mark a tier. If it is already marked, the next free tier above it is marked instead,
+    /// so stacking the same tier twice pushes the status higher, up to tier 6.
+    pub fn add_tier(&mut self, tier: u8) -> Result<(), StatusError> {
+        let mut tier = Self::validate_tier(tier)?;
+        while self.tiers.contains(&tier) && tier < MAX_TIER {
+            tier += 1;
+        }
+        self.tiers.insert(tier);
+        Ok(())
+    }
+
+    /// Shift every marked tier down by the given amount, dropping any that fall to 0.
+    pub fn reduce(&mut self, amount: u8) {
+        self.tiers = self.tiers.iter().filter(|&&tier| tier > amount).map(|&tier| tier - amount).collect();
+    }
+
+    /// Unmark a specific tier, returning false if it wasn't marked.
+    pub fn remove_tier(&mut self, tier: u8) -> bool {
+        self.tiers.remove(&tier)
+    }
+
+    /// Whether another status has the same name, ignoring case.
+    pub fn same_status(&self, other: &Status) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name)
+    }
+
+    /// Stack another status of the same name onto this one, adding its tiers from lowest to highest.
+    pub fn merge(&mut self, other: &Status) -> Result<(), StatusError> {
+        if !self.same_status(other) {
+            return Err(StatusError::NameMismatch(other.name.clone(), self.name.clone()));
+        }
+        for tier in other.tiers() {
+            self.add_tier(tier)?;
+        }
+        Ok(())
+    }
+
+    /// Get the name of the current tier, such as "Serious".
+    pub fn tier_name(&self) -> &'static str {
+        TIER_NAMES[usize::from(self.tier().min(MAX_TIER))]
+    }
+
+    /// Get a label for the status and the name of its tier, such as "poisoned-3 (Serious)".
+    pub fn label(&self) -> String {
+        format!("{self} ({})", self.tier_name())
+    }
+}
+
+/// Display the status in a human-readable format, such as "poisoned-3".
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.name, self.tier())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiers(status: &Status) -> Vec<u8> {
+        status.tiers().collect()
+    }
+
+    #[test]
+    fn new_rejects_tiers_outside_one_to_six() {
+        assert_eq!(Status::new("poisoned", 0), Err(StatusError::InvalidTier(0)));
+        assert_eq!(Status::new("poisoned", 7), Err(StatusError::InvalidTier(7)));
+        assert_eq!(Status::new("poisoned", 6).unwrap().tier(), 6);
+    }
+
+    #[test]
+    fn higher_tier_raises_status() {
+        let mut status = Status::new("poisoned", 2).unwrap();
+        status.add_tier(4).unwrap();
+        assert_eq!(tiers(&status), vec![2, 4]);
+        assert_eq!(status.tier(), 4);
+    }
+
+    #[test]
+    fn lower_tier_is_marked_without_raising_status() {
+        let mut status = Status::new("poisoned", 3).unwrap();
+        status.add_tier(1).unwrap();
+        assert_eq!(tiers(&status), vec![1, 3]);
+        assert_eq!(status.tier(), 3);
+    }
+
+    #[test]
+    fn stacking_a_marked_tier_bumps_to_next_free_tier() {
+        let mut status = Status::new("poisoned", 2).unwrap();
+        status.add_tier(3).unwrap();
+        status.add_tier(2).unwrap();
+        assert_eq!(tiers(&status), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn stacking_stops_at_tier_six() {
+        let mut status = Status::new("poisoned", 6).unwrap();
+        status.add_tier(6).unwrap();
+        assert_eq!(tiers(&status), vec![6]);
+        assert_eq!(status.add_tier(7), Err(StatusError::InvalidTier(7)));
+    }
+
+    #[test]
+    fn reduce_shifts_tiers_down_and_clears() {
+        let mut status = Status::new("poisoned", 1).unwrap();
+        status.add_tier(3).unwrap();
+        status.add_tier(5).unwrap();
+        status.reduce(2);
+        assert_eq!(tiers(&status), vec![1, 3]);
+        status.reduce(3);
+        assert!(status.is_cleared());
+        assert_eq!(status.tier(), 0);
+    }
+
+    #[test]
+    fn remove_tier_only_removes_that_tier() {
+        let mut status = Status::new("poisoned", 2).unwrap();
+        status.add_tier(4).unwrap();
+        assert!(status.remove_tier(4));
+        assert!(!status.remove_tier(4));
+        assert_eq!(tiers(&status), vec![2]);
+    }
+
+    #[test]
+    fn merge_stacks_tiers_from_lowest() {
+        let mut status = Status::new("Poisoned", 2).unwrap();
+        let mut other = Status::new("poisoned", 2).unwrap();
+        other.add_tier(3).unwrap();
+        status.merge(&other).unwrap();
+        assert_eq!(tiers(&status), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn merge_rejects_different_statuses() {
+        let mut status = Status::new("poisoned", 2).unwrap();
+        let other = Status::new("scared", 1).unwrap();
+        assert!(matches!(status.merge(&other), Err(StatusError::NameMismatch(..))));
+        assert_eq!(tiers(&status), vec![2]);
+    }
+
+    #[test]
+    fn deserializing_rejects_tiers_outside_one_to_six() {
+        assert!(serde_json::from_str::<Status>(r#"{"name":"poisoned","tiers":[9]}"#).is_err());
+        assert!(serde_json::from_str::<Status>(r#"{"name":"poisoned","tiers":[0,2]}"#).is_err());
+        let status = serde_json::from_str::<Status>(r#"{"name":"poisoned","tiers":[2,6]}"#).unwrap();
+        assert_eq!(tiers(&status), vec![2, 6]);
+    }
+
+    #[test]
+    fn deserializing_a_status_tag_rejects_bad_tiers() {
+        assert!(serde_json::from_str::<crate::tag::Tag>(r#"{"Status":{"name":"poisoned","tiers":[9]}}"#).is_err());
+    }
+
+    #[test]
+    fn label_names_the_current_tier() {
+        let mut status = Status::new("poisoned", 3).unwrap();
+        assert_eq!(status.label(), "poisoned-3 (Serious)");
+        status.add_tier(6).unwrap();
+        assert_eq!(status.tier_name(), "Taken out");
+    }
+}
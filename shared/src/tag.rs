@@ -1,17 +1,155 @@
-use std::collections::HashSet;
-
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::status::{Status, StatusError};
 
 /// A tag represents a characteristic, trait, or condition that can affect a character's abilities in the game.
+/// Every tag has a stable id, so tags that share a name stay distinct and survive being renamed.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum Tag {
-    Power { name: String, is_scratched: bool },
-    Weakness { name: String },
-    Story { name: String, is_scratched: bool },
-    Status { name: String, tiers: HashSet<u8> },
+    Power { #[serde(default = "Uuid::new_v4")] id: Uuid, name: String, is_scratched: bool },
+    Weakness { #[serde(default = "Uuid::new_v4")] id: Uuid, name: String },
+    Story { #[serde(default = "Uuid::new_v4")] id: Uuid, name: String, is_scratched: bool, #[serde(default)] duration: TagDuration },
+    Status { #[serde(default = "Uuid::new_v4")] id: Uuid, #[serde(flatten)] status: Status, #[serde(default)] duration: TagDuration },
+}
+
+/// How long a story tag or status lasts before it expires.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum TagDuration {
+    /// Expires once it has been used in a roll.
+    SingleUse,
+    /// Expires when the scene ends.
+    Scene,
+    /// Expires when the session ends.
+    Session,
+    #[default]
+    Permanent,
+}
+
+impl TagDuration {
+    /// Every duration, from shortest to longest.
+    pub const ALL: [TagDuration; 4] = [TagDuration::SingleUse, TagDuration::Scene, TagDuration::Session, TagDuration::Permanent];
+}
+
+/// Display the duration in a human-readable format.
+impl std::fmt::Display for TagDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagDuration::SingleUse => write!(f, "single use"),
+            TagDuration::Scene => write!(f, "until the scene ends"),
+            TagDuration::Session => write!(f, "until the session ends"),
+            TagDuration::Permanent => write!(f, "permanent"),
+        }
+    }
+}
+
+/// Why tags expired.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum ExpiryReason {
+    Used,
+    SceneEnded,
+    SessionEnded,
+}
+
+impl ExpiryReason {
+    /// Whether a tag with the given duration expires for this reason.
+    pub fn expires(&self, duration: TagDuration) -> bool {
+        match self {
+            ExpiryReason::Used => duration == TagDuration::SingleUse,
+            ExpiryReason::SceneEnded => duration == TagDuration::Scene,
+            ExpiryReason::SessionEnded => matches!(duration, TagDuration::Scene | TagDuration::Session),
+        }
+    }
+}
+
+/// Where a tag lives: on a hero or challenge (by name), in the scene, or on the fellowship.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum TagScope {
+    Hero(String),
+    Scene,
+    Challenge(String),
+    Fellowship,
+}
+
+/// A reference to a particular tag, by its scope and id.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub struct TagRef {
+    pub scope: TagScope,
+    pub id: Uuid,
+}
+
+impl TagRef {
+    pub fn new(scope: TagScope, id: Uuid) -> Self {
+        TagRef { scope, id }
+    }
+}
+
+/// Display the scope in a human-readable format.
+impl std::fmt::Display for TagScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagScope::Hero(name) | TagScope::Challenge(name) => write!(f, "{name}"),
+            TagScope::Scene => write!(f, "the scene"),
+            TagScope::Fellowship => write!(f, "the fellowship"),
+        }
+    }
 }
 
 impl Tag {
+    /// Get the stable id of the tag.
+    pub fn id(&self) -> Uuid {
+        match self {
+            Tag::Power { id, .. } | Tag::Weakness { id, .. } | Tag::Story { id, .. } | Tag::Status { id, .. } => *id,
+        }
+    }
+
+    /// Get the display name of the tag.
+    pub fn name(&self) -> &str {
+        match self {
+            Tag::Power { name, .. }
+            | Tag::Weakness { name, .. }
+            | Tag::Story { name, .. } => name,
+            Tag::Status { status, .. } => &status.name,
+        }
+    }
+
+    /// Get the status, if the tag is one.
+    pub fn status(&self) -> Option<&Status> {
+        match self {
+            Tag::Status { status, .. } => Some(status),
+            _ => None,
+        }
+    }
+
+    /// Get the status mutably, if the tag is one.
+    pub fn status_mut(&mut self) -> Option<&mut Status> {
+        match self {
+            Tag::Status { status, .. } => Some(status),
+            _ => None,
+        }
+    }
+
+    /// Get how long the tag lasts. Power and weakness tags are always permanent.
+    pub fn duration(&self) -> TagDuration {
+        match self {
+            Tag::Story { duration, .. } | Tag::Status { duration, .. } => *duration,
+            Tag::Power { .. } | Tag::Weakness { .. } => TagDuration::Permanent,
+        }
+    }
+
+    /// Set how long a story tag or status lasts.
+    pub fn with_duration(mut self, new_duration: TagDuration) -> Self {
+        if let Tag::Story { duration, .. } | Tag::Status { duration, .. } = &mut self {
+            *duration = new_duration;
+        }
+        self
+    }
+
+    /// Whether the tag is a status with no remaining tiers.
+    pub fn is_cleared(&self) -> bool {
+        matches!(self, Tag::Status { status, .. } if status.is_cleared())
+    }
+
     /// Get the value of the tag for roll calculations.
     pub fn get_value(&self) -> u8 {
         match self {
@@ -23,38 +161,7 @@ impl Tag {
                 }
             }
             Tag::Weakness { .. } => 1,
-            Tag::Status { tiers, .. } => {
-                if let Some(highest) = tiers.iter().max() {
-                    *highest
-                } else {
-                    0
-                }
-            }
-        }
-    }
-
-    /// Add a tier to a status tag, up to a maximum of 6.
-    pub fn add_tier(&mut self, mut tier: u8) {
-        // add a tier to a status tag, up to a maximum of 6
-        match self {
-            Tag::Status { tiers, .. } => {
-                while tiers.contains(&tier) && tier < 6 {
-                    tier += 1;
-                }
-                tiers.insert(tier);
-            }
-            _ => (),
-        }
-    }
-
-    /// Decrease the tier of a status tag by 1, removing any that reach 0.
-    pub fn decrease_tier(&mut self) {
-        // decrease all tiers by 1, removing any that reach 0
-        match self {
-            Tag::Status { tiers, .. } => {
-                *tiers = tiers.iter().filter(|&&t| t > 1).map(|&t| t - 1).collect();
-            }
-            _ => (),
+            Tag::Status { status, .. } => status.tier(),
         }
     }
 
@@ -81,6 +188,7 @@ impl Tag {
     /// Create a new power tag with the given name.
     pub fn new_power(name: &str) -> Self {
         Tag::Power {
+            id: Uuid::new_v4(),
             name: name.to_string(),
             is_scratched: false,
         }
@@ -89,24 +197,52 @@ impl Tag {
     /// Create a new weakness tag with the given name.
     pub fn new_weakness(name: &str) -> Self {
         Tag::Weakness {
+            id: Uuid::new_v4(),
             name: name.to_string(),
         }
     }
 
-    /// Create a new story tag with the given name.
+    /// Create a new permanent story tag with the given name.
     pub fn new_story(name: &str) -> Self {
         Tag::Story {
+            id: Uuid::new_v4(),
             name: name.to_string(),
             is_scratched: false,
+            duration: TagDuration::default(),
         }
     }
 
-    /// Create a new status tag with the given name and initial tier.
-    pub fn new_status(name: &str, initial_tier: usize) -> Self {
-        Tag::Status {
-            name: name.to_string(),
-            tiers: HashSet::from([initial_tier as u8]),
+    /// Create a new status tag with the given name and initial tier, which must be between 1 and 6.
+    pub fn new_status(name: &str, initial_tier: u8) -> Result<Self, StatusError> {
+        Ok(Tag::from(Status::new(name, initial_tier)?))
+    }
+}
+
+impl From<Status> for Tag {
+    fn from(status: Status) -> Self {
+        Tag::Status { id: Uuid::new_v4(), status, duration: TagDuration::default() }
+    }
+}
+
+/// Give a status of the given tier to a collection of statuses, stacking onto an existing status of the same name.
+pub fn inflict_status(statuses: &mut Vec<Tag>, name: &str, tier: u8) -> Result<(), StatusError> {
+    let inflicted = Status::new(name, tier)?;
+    match statuses.iter_mut().filter_map(Tag::status_mut).find(|status| status.same_status(&inflicted)) {
+        Some(status) => status.merge(&inflicted),
+        None => {
+            statuses.push(Tag::from(inflicted));
+            Ok(())
         }
     }
 }
 
+/// Reduce the status with the given id in a collection by the given number of tiers, removing it once cleared.
+/// Returns false if there is no such status.
+pub fn reduce_status(statuses: &mut Vec<Tag>, id: Uuid, amount: u8) -> bool {
+    let Some(status) = statuses.iter_mut().find(|status| status.id() == id).and_then(Tag::status_mut) else {
+        return false;
+    };
+    status.reduce(amount);
+    statuses.retain(|status| !status.is_cleared());
+    true
+}
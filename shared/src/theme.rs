@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{might::Might, tag::Tag};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Theme {
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub name: String,
+    #[serde(default)]
+    pub themebook: String,
     pub power_tags: Vec<Tag>,
     pub weakness_tags: Vec<Tag>,
     pub might: Might,
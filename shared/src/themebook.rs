@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{hero::Hero, might::Might, tag::Tag, theme::Theme};
+
+/// The number of themes a new hero starts with.
+pub const HERO_THEMES: usize = 4;
+/// The number of power tags, including the title tag, a new theme starts with.
+pub const STARTING_POWER_TAGS: usize = 3;
+/// The number of weakness tags a new theme starts with.
+pub const STARTING_WEAKNESS_TAGS: usize = 1;
+
+/// A template describing how to build a theme of a particular kind.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Themebook {
+    pub name: String,
+    pub might: Might,
+    pub description: String,
+    pub power_tag_questions: Vec<String>,
+    pub weakness_tag_questions: Vec<String>,
+    pub quest_prompts: Vec<String>,
+    pub special_improvements: Vec<String>,
+}
+
+/// Reasons a hero or theme can't be created.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CreationError {
+    MissingName,
+    MissingQuest(String),
+    WrongThemeCount(usize),
+    WrongPowerTagCount(String, usize),
+    WrongWeaknessTagCount(String, usize),
+}
+
+impl std::fmt::Display for CreationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CreationError::MissingName => write!(f, "every hero and theme needs a name"),
+            CreationError::MissingQuest(theme) => write!(f, "{theme} needs a quest"),
+            CreationError::WrongThemeCount(count) => write!(f, "a hero needs {HERO_THEMES} themes, not {count}"),
+            CreationError::WrongPowerTagCount(theme, count) => {
+                write!(f, "{theme} needs {STARTING_POWER_TAGS} power tags, not {count}")
+            }
+            CreationError::WrongWeaknessTagCount(theme, count) => {
+                write!(f, "{theme} needs {STARTING_WEAKNESS_TAGS} weakness tags, not {count}")
+            }
+        }
+    }
+}
+
+impl Themebook {
+    /// Create a new theme from this themebook, with the answers to its power and weakness tag questions.
+    pub fn create_theme(
+        &self,
+        name: &str,
+        power_tags: &[String],
+        weakness_tags: &[String],
+        quest: &str,
+    ) -> Result<Theme, CreationError> {
+        let power_tags: Vec<&String> = power_tags.iter().filter(|tag| !tag.trim().is_empty()).collect();
+        let weakness_tags: Vec<&String> = weakness_tags.iter().filter(|tag| !tag.trim().is_empty()).collect();
+        if name.trim().is_empty() {
+            return Err(CreationError::MissingName);
+        }
+        if power_tags.len() != STARTING_POWER_TAGS {
+            return Err(CreationError::WrongPowerTagCount(name.to_string(), power_tags.len()));
+        }
+        if weakness_tags.len() != STARTING_WEAKNESS_TAGS {
+            return Err(CreationError::WrongWeaknessTagCount(name.to_string(), weakness_tags.len()));
+        }
+        if quest.trim().is_empty() {
+            return Err(CreationError::MissingQuest(name.to_string()));
+        }
+        Ok(Theme {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            themebook: self.name.clone(),
+            power_tags: power_tags.into_iter().map(|tag| Tag::new_power(tag)).collect(),
+            weakness_tags: weakness_tags.into_iter().map(|tag| Tag::new_weakness(tag)).collect(),
+            might: self.might.clone(),
+            quest: quest.to_string(),
+            improve: 0,
+            abandon: 0,
+            milestone: 0,
+            special_improvements: vec![],
+        })
+    }
+}
+
+/// Create a new hero from the themes built during hero creation.
+pub fn create_hero(name: &str, player: &str, themes: Vec<Theme>) -> Result<Hero, CreationError> {
+    if name.trim().is_empty() {
+        return Err(CreationError::MissingName);
+    }
+    if themes.len() != HERO_THEMES {
+        return Err(CreationError::WrongThemeCount(themes.len()));
+    }
+    Ok(Hero {
+        name: name.to_string(),
+        player: player.to_string(),
+        themes,
+        backpack: vec![],
+        statuses: vec![],
+    })
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    challenge::Challenge,
+    limit::Limit,
+    messaging::SystemResponse,
+    scene::Scene,
+    tag::{Tag, TagRef, TagScope},
+};
+
+/// The name players see for a tag they know is there but haven't learned yet.
+const UNKNOWN_TAG: &str = "???";
+
+/// How much of a challenge, tag or limit the players can see, from least to most. The narrator always sees everything.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Visibility {
+    /// Players don't know it exists.
+    Hidden,
+    /// Players know it exists, but not its details: a challenge's rating, threats and loot,
+    /// a limit's progress and maximum, or a tag's name.
+    Partial,
+    #[default]
+    Revealed,
+}
+
+impl Visibility {
+    /// Get the visibility of a tag, which is revealed unless set otherwise.
+    pub fn of_tag(visibility: &HashMap<Uuid, Visibility>, tag: &Tag) -> Self {
+        visibility.get(&tag.id()).copied().unwrap_or_default()
+    }
+}
+
+/// Display the visibility in a human-readable format.
+impl std::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Visibility::Hidden => write!(f, "hidden"),
+            Visibility::Partial => write!(f, "partially revealed"),
+            Visibility::Revealed => write!(f, "revealed"),
+        }
+    }
+}
+
+/// Something the narrator can hide or reveal.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum Secret {
+    Challenge(String),
+    /// A scene story tag, or one of a challenge's tags or statuses.
+    Tag(TagRef),
+    Limit { challenge: String, limit: String },
+}
+
+/// Filter tags down to what players can see, hiding names of partially revealed tags.
+fn visible_tags(tags: &[Tag], visibility: &HashMap<Uuid, Visibility>) -> Vec<Tag> {
+    tags.iter()
+        .filter_map(|tag| match Visibility::of_tag(visibility, tag) {
+            Visibility::Hidden => None,
+            Visibility::Partial => Some(redact_tag(tag)),
+            Visibility::Revealed => Some(tag.clone()),
+        })
+        .collect()
+}
+
+/// Replace a tag's name with a placeholder, keeping its id so it can still be referred to.
+fn redact_tag(tag: &Tag) -> Tag {
+    let mut tag = tag.clone();
+    match &mut tag {
+        Tag::Power { name, .. } | Tag::Weakness { name, .. } | Tag::Story { name, .. } => *name = UNKNOWN_TAG.to_string(),
+        Tag::Status { status, .. } => status.name = UNKNOWN_TAG.to_string(),
+    }
+    tag
+}
+
+/// Get what players can see of a limit.
+fn visible_limit(limit: &Limit) -> Option<Limit> {
+    match limit.visibility {
+        Visibility::Hidden => None,
+        Visibility::Partial => Some(Limit { progress: 0, max: 0, statuses: vec![], ..limit.clone() }),
+        Visibility::Revealed => Some(limit.clone()),
+    }
+}
+
+impl Challenge {
+    /// Get what players can see of the challenge, if they know about it at all.
+    pub fn visible_to_players(&self) -> Option<Challenge> {
+        let mut challenge = match self.visibility {
+            Visibility::Hidden => return None,
+            Visibility::Partial => Challenge { rating: 0, threats: vec![], loot: vec![], ..self.clone() },
+            Visibility::Revealed => self.clone(),
+        };
+        challenge.tags = visible_tags(&self.tags, &self.tag_visibility);
+        challenge.statuses = visible_tags(&self.statuses, &self.tag_visibility);
+        challenge.limits = self.limits.iter().filter_map(visible_limit).collect();
+        challenge.tag_visibility.clear();
+        Some(challenge)
+    }
+}
+
+impl Scene {
+    /// Get what players can see of the scene.
+    pub fn visible_to_players(&self) -> Scene {
+        Scene {
+            name: self.name.clone(),
+            story_tags: visible_tags(&self.story_tags, &self.tag_visibility),
+            challenges: self.challenges.iter().filter_map(Challenge::visible_to_players).collect(),
+            tag_visibility: HashMap::new(),
+        }
+    }
+
+    /// Get how much players can see of a challenge, which is revealed if there is no such challenge.
+    pub fn challenge_visibility(&self, name: &str) -> Visibility {
+        self.challenge(name).map_or(Visibility::Revealed, |challenge| challenge.visibility)
+    }
+
+    /// Get how much players can see of a tag, which is no more than they can see of the challenge it's on.
+    pub fn tag_visibility(&self, scope: &TagScope, tag: &Tag) -> Visibility {
+        match scope {
+            TagScope::Scene => Visibility::of_tag(&self.tag_visibility, tag),
+            TagScope::Challenge(name) => self.challenge(name).map_or(Visibility::Revealed, |challenge| {
+                challenge.visibility.min(Visibility::of_tag(&challenge.tag_visibility, tag))
+            }),
+            _ => Visibility::Revealed,
+        }
+    }
+}
+
+impl SystemResponse {
+    /// Get the version of the response a player should see, or `None` if it gives away something hidden.
+    /// The narrator sees every response as it is.
+    pub fn visible_to(self, role: &str) -> Option<SystemResponse> {
+        if role == "narrator" {
+            return Some(self);
+        }
+        match self {
+            SystemResponse::SceneUpdate { scene } => Some(SystemResponse::SceneUpdate { scene: scene.visible_to_players() }),
+            SystemResponse::ChallengeUpdate { challenge } => {
+                challenge.visible_to_players().map(|challenge| SystemResponse::ChallengeUpdate { challenge })
+            }
+            SystemResponse::Resync { settings, scene, heroes, members, missed } => Some(SystemResponse::Resync {
+                settings,
+                scene: scene.visible_to_players(),
+                heroes,
+                members,
+                missed,
+            }),
+            SystemResponse::StateRestored { settings, scene, heroes } => {
+                Some(SystemResponse::StateRestored { settings, scene: scene.visible_to_players(), heroes })
+            }
+            SystemResponse::LimitReached { limit, challenge_visibility, .. }
+                if limit.visibility != Visibility::Revealed || challenge_visibility == Visibility::Hidden =>
+            {
+                None
+            }
+            SystemResponse::LootDropped { challenge_visibility: Visibility::Hidden, .. } => None,
+            SystemResponse::LootDropped { challenge, tags, challenge_visibility: Visibility::Partial } => Some(SystemResponse::LootDropped {
+                challenge,
+                tags: vec![UNKNOWN_TAG.to_string(); tags.len()],
+                challenge_visibility: Visibility::Partial,
+            }),
+            SystemResponse::PowerSpent { username, roll_id, effects, remaining, challenge_visibility } => {
+                let effects = effects
+                    .into_iter()
+                    .filter(|effect| effect.challenge().and_then(|name| challenge_visibility.get(name)) != Some(&Visibility::Hidden))
+                    .collect();
+                Some(SystemResponse::PowerSpent { username, roll_id, effects, remaining, challenge_visibility: HashMap::new() })
+            }
+            SystemResponse::Revealed { visibility: Visibility::Hidden, .. } => None,
+            SystemResponse::Revealed { challenge_visibility: Visibility::Hidden, .. } => None,
+            SystemResponse::TagsExpired { reason, tags, tag_visibility } => {
+                let tags: Vec<(TagScope, Tag)> = tags
+                    .into_iter()
+                    .filter_map(|(scope, tag)| match Visibility::of_tag(&tag_visibility, &tag) {
+                        Visibility::Hidden => None,
+                        Visibility::Partial => Some((scope, redact_tag(&tag))),
+                        Visibility::Revealed => Some((scope, tag)),
+                    })
+                    .collect();
+                (!tags.is_empty()).then(|| SystemResponse::TagsExpired { reason, tags, tag_visibility: HashMap::new() })
+            }
+            response => Some(response),
+        }
+    }
+}